@@ -0,0 +1,10 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+// The value given on the command line as --name=VALUE, parsed, or None if there isn't one.
+pub fn value<T>(name: &str) -> Result<Option<T>, String> where T: FromStr, T::Err: Display {
+    let prefix = format!("--{}=", name);
+    std::env::args()
+        .find_map(|x| x.strip_prefix(&prefix).map(|x| x.parse::<T>()))
+        .transpose().map_err(|e| format!("--{}: {}", name, e))
+}
@@ -1,38 +1,24 @@
 use std::io::BufRead;
 
-fn main() {
+use day10::Check;
+
+fn main() -> Result<(), String> {
     let stdin = std::io::stdin();
+    let syntax = day10::cli::syntax()?;
 
     let mut illegal = vec![];
+    let mut score = 0;
 
     for line in stdin.lock().lines() {
-        let line = line.expect("read line");
-
-        let mut stack = vec![];
+        let line = line.map_err(|e| e.to_string())?;
 
-        for char in line.chars() {
-            match char {
-                '[' => stack.push(']'),
-                '{' => stack.push('}'),
-                '(' => stack.push(')'),
-                '<' => stack.push('>'),
-                _ => {
-                    let expected = stack.pop().unwrap();
-                    if char != expected {
-                        println!("{} - Expected {}, but found {} instead.", line, expected, char);
-                        illegal.push(char);
-                    }
-                }
-            }
+        if let Check::Corrupted(error) = syntax.check(&line) {
+            println!("{} - {}", line, error);
+            illegal.push(error.found());
+            score += syntax.error_score(&error);
         }
     }
 
-    let score: u32 = illegal.iter().map(|x| match x {
-        ')' => 3,
-        ']' => 57,
-        '}' => 1197,
-        '>' => 25137,
-        _ => unreachable!(),
-    }).sum();
     println!("{:?} score={}", illegal, score);
+    Ok(())
 }
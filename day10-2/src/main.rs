@@ -1,46 +1,55 @@
 use std::io::BufRead;
 
-fn main() {
+use day10::{middle_score, Check, Scores};
+
+// With --repair, every line that isn't complete is printed fixed, along with both kinds of score.
+fn main() -> Result<(), String> {
     let stdin = std::io::stdin();
+    let syntax = day10::cli::syntax()?;
+
+    if std::env::args().any(|x| x == "--repair") {
+        let mut scores = Scores::default();
+        for line in stdin.lock().lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            let check = syntax.check(&line);
+            scores.add(&syntax, &check);
+            if check == Check::Complete {
+                continue;
+            }
+            let repair = syntax.repair(&line);
+            let mut changes = repair.edits.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+            if !repair.completion.is_empty() {
+                changes.push(format!("complete with {}", repair.completion));
+            }
+            println!("{} -> {} ({})", line, repair.fixed, changes.join(", "));
+        }
+        println!("syntax error score: {}", scores.syntax_error);
+        match scores.middle() {
+            Some(middle) => println!("middle score: {}", middle),
+            None => println!("middle score: none"),
+        }
+        return Ok(());
+    }
 
     let mut autocomplete_scores = vec![];
 
-    'lines: for line in stdin.lock().lines() {
-        let line = line.expect("read line");
-
-        let mut stack = vec![];
-
-        for char in line.chars() {
-            match char {
-                '[' => stack.push(']'),
-                '{' => stack.push('}'),
-                '(' => stack.push(')'),
-                '<' => stack.push('>'),
-                _ => {
-                    let expected = stack.pop().unwrap();
-                    if char != expected {
-                        println!("{} - Expected {}, but found {} instead.", line, expected, char);
-                        continue 'lines;
-                    }
-                }
-            }
-        }
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
 
-        let mut score: i64 = 0;
-        for item in stack.iter().rev() {
-            score = score * 5 + match item {
-                ')' => 1,
-                ']' => 2,
-                '}' => 3,
-                '>' => 4,
-                _ => unreachable!(),
-            };
+        match syntax.check(&line) {
+            Check::Corrupted(error) => println!("{} - {}", line, error),
+            Check::Incomplete { completion } => {
+                let score = syntax.completion_score(&completion);
+                autocomplete_scores.push((completion, score));
+            }
+            Check::Complete => {}
         }
-        autocomplete_scores.push((stack.iter().rev().collect::<String>(), score));
     }
 
-    autocomplete_scores.sort_by(|(_, score1), (_, score2)| score1.cmp(score2));
+    autocomplete_scores.sort_by_key(|x| x.1);
 
     println!("{:?}", autocomplete_scores);
-    println!("middle score: {}", (autocomplete_scores[autocomplete_scores.len() / 2 as usize].1));
+    let scores = autocomplete_scores.iter().map(|x| x.1).collect::<Vec<u128>>();
+    println!("middle score: {}", middle_score(&scores).ok_or("no incomplete lines")?);
+    Ok(())
 }
@@ -0,0 +1,16 @@
+use crate::Syntax;
+
+// The delimiters from the table given with --delimiters=PATH (see Syntax's FromStr), or the usual
+// four. --skip-unknown passes over characters that aren't delimiters instead of calling the line
+// corrupted.
+pub fn syntax() -> Result<Syntax, String> {
+    let mut syntax = match args::value::<String>("delimiters")? {
+        Some(path) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("{}: {}", path, e))?
+            .parse::<Syntax>()
+            .map_err(|e| format!("{}: {}", path, e))?,
+        None => Syntax::default(),
+    };
+    syntax.skip_unknown = std::env::args().any(|x| x == "--skip-unknown");
+    Ok(syntax)
+}
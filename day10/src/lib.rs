@@ -0,0 +1,285 @@
+pub mod cli;
+pub mod repair;
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delimiter {
+    pub open: char,
+    pub close: char,
+    // Points for a line corrupted by this closer turning up where it shouldn't.
+    pub error_score: u64,
+    // Points for each of these closers an incomplete line needs.
+    pub completion_score: u64,
+}
+
+// Which delimiters there are, and what to do about characters that aren't any of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Syntax {
+    delimiters: Vec<Delimiter>,
+    // Pass over other characters instead of calling the line corrupted.
+    pub skip_unknown: bool,
+}
+
+impl Syntax {
+    pub fn new(delimiters: Vec<Delimiter>, skip_unknown: bool) -> Result<Syntax, String> {
+        let mut seen = vec![];
+        for delimiter in delimiters.iter() {
+            for char in [delimiter.open, delimiter.close] {
+                if seen.contains(&char) {
+                    return Err(format!("delimiter {} used more than once", char));
+                }
+                seen.push(char);
+            }
+        }
+        Ok(Syntax { delimiters, skip_unknown })
+    }
+
+    pub fn delimiters(&self) -> &[Delimiter] {
+        &self.delimiters
+    }
+
+    pub fn opener(&self, char: char) -> Option<&Delimiter> {
+        self.delimiters.iter().find(|x| x.open == char)
+    }
+
+    pub fn closer(&self, char: char) -> Option<&Delimiter> {
+        self.delimiters.iter().find(|x| x.close == char)
+    }
+
+    // Reads the line left to right, keeping a stack of the closers it's waiting for, and stops at the
+    // first character that doesn't fit.
+    pub fn check(&self, line: &str) -> Check {
+        let mut stack = vec![];
+        for (idx, char) in line.chars().enumerate() {
+            let column = idx + 1;
+            if let Some(delimiter) = self.opener(char) {
+                stack.push(delimiter.close);
+            } else if self.closer(char).is_some() {
+                match stack.pop() {
+                    Some(expected) if expected == char => {}
+                    Some(expected) => return Check::Corrupted(SyntaxError::Mismatched { column, expected, found: char }),
+                    None => return Check::Corrupted(SyntaxError::Unopened { column, found: char }),
+                }
+            } else if !self.skip_unknown {
+                return Check::Corrupted(SyntaxError::Unknown { column, found: char });
+            }
+        }
+        match stack.is_empty() {
+            true => Check::Complete,
+            false => Check::Incomplete { completion: stack.iter().rev().collect() },
+        }
+    }
+
+    // The found closer's points. Characters that aren't delimiters aren't worth any.
+    pub fn error_score(&self, error: &SyntaxError) -> u64 {
+        match error {
+            SyntaxError::Mismatched { found, .. } | SyntaxError::Unopened { found, .. } => self.closer(*found).map_or(0, |x| x.error_score),
+            SyntaxError::Unknown { .. } => 0,
+        }
+    }
+
+    // Five times the score so far plus the closer's points, for each closer in turn.
+    pub fn completion_score(&self, completion: &str) -> u128 {
+        completion.chars()
+            .map(|x| self.closer(x).map_or(0, |x| x.completion_score))
+            .fold(0u128, |acc, x| acc.saturating_mul(5).saturating_add(x as u128))
+    }
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        let delimiters = [('(', ')', 3, 1), ('[', ']', 57, 2), ('{', '}', 1197, 3), ('<', '>', 25137, 4)]
+            .into_iter()
+            .map(|(open, close, error_score, completion_score)| Delimiter { open, close, error_score, completion_score })
+            .collect();
+        Syntax { delimiters, skip_unknown: false }
+    }
+}
+
+// One delimiter per line: the opener, the closer, the error score and the completion score, e.g.
+// "( ) 3 1". Blank lines and lines starting with # are skipped.
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut delimiters = vec![];
+        for (idx, line) in s.lines().enumerate().map(|(idx, x)| (idx + 1, x.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts = line.split_whitespace().collect::<Vec<&str>>();
+            let char = |x: &str| {
+                let mut chars = x.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => Ok(char),
+                    _ => Err(format!("line {}: '{}' should be a single char", idx, x)),
+                }
+            };
+            let score = |x: &str| x.parse::<u64>().map_err(|e| format!("line {}: score '{}': {}", idx, x, e));
+            match parts[..] {
+                [open, close, error_score, completion_score] => delimiters.push(Delimiter {
+                    open: char(open)?,
+                    close: char(close)?,
+                    error_score: score(error_score)?,
+                    completion_score: score(completion_score)?,
+                }),
+                _ => return Err(format!("line {}: expected opener, closer, error score and completion score", idx)),
+            }
+        }
+        if delimiters.is_empty() {
+            return Err("no delimiters".to_string());
+        }
+        Syntax::new(delimiters, false)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxError {
+    // A closer that isn't the one for the innermost open delimiter.
+    Mismatched { column: usize, expected: char, found: char },
+    // A closer with nothing open.
+    Unopened { column: usize, found: char },
+    // Not a delimiter at all.
+    Unknown { column: usize, found: char },
+}
+
+impl SyntaxError {
+    // Counted from 1.
+    pub fn column(&self) -> usize {
+        match *self {
+            SyntaxError::Mismatched { column, .. } | SyntaxError::Unopened { column, .. } | SyntaxError::Unknown { column, .. } => column,
+        }
+    }
+
+    pub fn found(&self) -> char {
+        match *self {
+            SyntaxError::Mismatched { found, .. } | SyntaxError::Unopened { found, .. } | SyntaxError::Unknown { found, .. } => found,
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxError::Mismatched { column, expected, found } => write!(f, "column {}: Expected {}, but found {} instead.", column, expected, found),
+            SyntaxError::Unopened { column, found } => write!(f, "column {}: Found {} with nothing open.", column, found),
+            SyntaxError::Unknown { column, found } => write!(f, "column {}: Found {}, which isn't a delimiter.", column, found),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Complete,
+    // The closers still needed, innermost first.
+    Incomplete { completion: String },
+    Corrupted(SyntaxError),
+}
+
+// Both kinds of score, added up a line at a time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scores {
+    pub syntax_error: u64,
+    pub autocomplete: Vec<u128>,
+}
+
+impl Scores {
+    pub fn add(&mut self, syntax: &Syntax, check: &Check) {
+        match check {
+            Check::Corrupted(error) => self.syntax_error += syntax.error_score(error),
+            Check::Incomplete { completion } => self.autocomplete.push(syntax.completion_score(completion)),
+            Check::Complete => {}
+        }
+    }
+
+    pub fn middle(&self) -> Option<u128> {
+        middle_score(&self.autocomplete)
+    }
+}
+
+// The middle of the autocomplete scores once sorted, as there's always an odd number of them.
+pub fn middle_score(scores: &[u128]) -> Option<u128> {
+    let mut scores = scores.to_vec();
+    scores.sort_unstable();
+    scores.get(scores.len() / 2).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{middle_score, Check, Delimiter, Scores, Syntax, SyntaxError};
+
+    const EXAMPLE: &str = "\
+[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]
+";
+
+    #[test]
+    fn example() {
+        let syntax = Syntax::default();
+        let checks = EXAMPLE.lines().map(|x| syntax.check(x)).collect::<Vec<Check>>();
+        let errors = checks.iter()
+            .filter_map(|x| match x {
+                Check::Corrupted(error) => Some(*error),
+                _ => None,
+            })
+            .collect::<Vec<SyntaxError>>();
+        assert_eq!(errors[0], SyntaxError::Mismatched { column: 13, expected: ']', found: '}' });
+        assert_eq!(errors.iter().map(|x| x.found()).collect::<String>(), "})])>");
+        assert_eq!(errors.iter().map(|x| syntax.error_score(x)).sum::<u64>(), 26397);
+
+        let scores = checks.iter()
+            .filter_map(|x| match x {
+                Check::Incomplete { completion } => Some(syntax.completion_score(completion)),
+                _ => None,
+            })
+            .collect::<Vec<u128>>();
+        assert_eq!(checks[0], Check::Incomplete { completion: "}}]])})]".to_string() });
+        assert_eq!(scores, vec![288957, 5566, 1480781, 995444, 294]);
+        assert_eq!(middle_score(&scores), Some(288957));
+
+        let mut all = Scores::default();
+        for check in checks.iter() {
+            all.add(&syntax, check);
+        }
+        assert_eq!((all.syntax_error, all.middle()), (26397, Some(288957)));
+    }
+
+    #[test]
+    fn edge_cases() {
+        let syntax = Syntax::default();
+        assert_eq!(syntax.check(""), Check::Complete);
+        assert_eq!(syntax.check("()[]"), Check::Complete);
+        assert_eq!(syntax.check(")("), Check::Corrupted(SyntaxError::Unopened { column: 1, found: ')' }));
+        assert_eq!(syntax.check("(a)"), Check::Corrupted(SyntaxError::Unknown { column: 2, found: 'a' }));
+        assert_eq!(syntax.error_score(&SyntaxError::Unopened { column: 1, found: '>' }), 25137);
+
+        let lenient = Syntax { skip_unknown: true, ..Syntax::default() };
+        assert_eq!(lenient.check("(a + b"), Check::Incomplete { completion: ")".to_string() });
+    }
+
+    #[test]
+    fn custom_delimiters() {
+        let syntax = "# Quotes that open and close differently\n« » 10 1\n( ) 1 2\n".parse::<Syntax>().unwrap();
+        assert_eq!(syntax.delimiters()[0], Delimiter { open: '«', close: '»', error_score: 10, completion_score: 1 });
+        assert_eq!(syntax.check("«(»"), Check::Corrupted(SyntaxError::Mismatched { column: 3, expected: ')', found: '»' }));
+        assert_eq!(syntax.check("(«"), Check::Incomplete { completion: "»)".to_string() });
+        assert_eq!(syntax.completion_score("»)"), 7);
+        // Round brackets aren't special any more.
+        assert_eq!(syntax.check("["), Check::Corrupted(SyntaxError::Unknown { column: 1, found: '[' }));
+
+        assert!("( ( 1 1\n".parse::<Syntax>().is_err());
+        assert!("( ) 1\n".parse::<Syntax>().is_err());
+        assert!("() ) 1 1\n".parse::<Syntax>().is_err());
+        assert!("".parse::<Syntax>().is_err());
+    }
+}
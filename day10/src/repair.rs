@@ -0,0 +1,257 @@
+use std::fmt;
+
+use crate::{Check, Syntax};
+
+// Columns are counted from 1 in the line as it was. Inserting at a column puts the new character
+// before the one that was there, or at the end for the column after the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Replace { column: usize, from: char, to: char },
+    Delete { column: usize, char: char },
+    Insert { column: usize, char: char },
+}
+
+impl Edit {
+    fn column(&self) -> usize {
+        match *self {
+            Edit::Replace { column, .. } | Edit::Delete { column, .. } | Edit::Insert { column, .. } => column,
+        }
+    }
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::Replace { column, from, to } => write!(f, "replace {} with {} at column {}", from, to, column),
+            Edit::Delete { column, char } => write!(f, "delete {} at column {}", char, column),
+            Edit::Insert { column, char } => write!(f, "insert {} at column {}", char, column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    // As few as will do, in column order.
+    pub edits: Vec<Edit>,
+    // The closers the edited line still needs.
+    pub completion: String,
+    // The edited line with the completion on the end.
+    pub fixed: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open(usize),
+    Close(usize),
+    Other,
+}
+
+impl Syntax {
+    fn token(&self, char: char) -> Token {
+        if let Some(idx) = self.delimiters.iter().position(|x| x.open == char) {
+            Token::Open(idx)
+        } else if let Some(idx) = self.delimiters.iter().position(|x| x.close == char) {
+            Token::Close(idx)
+        } else {
+            Token::Other
+        }
+    }
+
+    // The fewest replacements, deletions and insertions that leave the line uncorrupted, followed by
+    // whatever closers it then needs.
+    //
+    // An uncorrupted line is a run of balanced stretches with openers still waiting to be closed in
+    // between. balanced[i][j] is the fewest edits that balance tokens i..j: either token i is left
+    // without a partner, and gets deleted or has one inserted, or it pairs with some token k, which
+    // is free if they're an opener and its closer and otherwise takes fixing one or both. prefix[j]
+    // then splits the first j tokens into stretches, any leftover openers being free and anything
+    // else deleted. It's cubic in the length of the line.
+    pub fn repair(&self, line: &str) -> Repair {
+        let chars = line.chars().collect::<Vec<char>>();
+        // Characters the syntax skips over don't need fixing and stay where they are.
+        let tokens = chars.iter().copied().enumerate()
+            .map(|(idx, char)| (idx + 1, char, self.token(char)))
+            .filter(|x| !(self.skip_unknown && x.2 == Token::Other))
+            .collect::<Vec<(usize, char, Token)>>();
+        let n = tokens.len();
+
+        let pair_cost = |a: Token, b: Token| match (a, b) {
+            (Token::Open(x), Token::Close(y)) if x == y => 0,
+            (Token::Open(_), _) | (_, Token::Close(_)) => 1,
+            _ => 2,
+        };
+
+        let mut balanced = vec![vec![0usize; n + 1]; n + 1];
+        for len in 1..=n {
+            for i in 0..=n - len {
+                let j = i + len;
+                let mut best = balanced[i + 1][j] + 1;
+                for k in i + 1..j {
+                    best = best.min(pair_cost(tokens[i].2, tokens[k].2) + balanced[i + 1][k] + balanced[k + 1][j]);
+                }
+                balanced[i][j] = best;
+            }
+        }
+        let mut prefix = vec![0usize; n + 1];
+        for j in 1..=n {
+            let open = matches!(tokens[j - 1].2, Token::Open(_));
+            prefix[j] = prefix[j - 1] + if open { 0 } else { 1 };
+            for i in 0..j - 1 {
+                prefix[j] = prefix[j].min(prefix[i] + balanced[i][j]);
+            }
+        }
+
+        let mut edits = vec![];
+        let mut j = n;
+        while j > 0 {
+            let (column, char, token) = tokens[j - 1];
+            let open = matches!(token, Token::Open(_));
+            if prefix[j] == prefix[j - 1] + if open { 0 } else { 1 } {
+                if !open {
+                    edits.push(Edit::Delete { column, char });
+                }
+                j -= 1;
+                continue;
+            }
+            let i = (0..j - 1).find(|i| prefix[*i] + balanced[*i][j] == prefix[j]).unwrap();
+            self.trace(&tokens, &balanced, i, j, chars.len(), &mut edits);
+            j = i;
+        }
+        edits.sort_by_key(|x| (x.column(), !matches!(x, Edit::Insert { .. })));
+
+        let mut fixed = String::with_capacity(chars.len());
+        let mut edit = edits.iter().peekable();
+        for (idx, char) in chars.iter().copied().chain([' ']).enumerate() {
+            let column = idx + 1;
+            let mut kept = Some(char);
+            while let Some(next) = edit.next_if(|x| x.column() == column) {
+                match *next {
+                    Edit::Insert { char, .. } => fixed.push(char),
+                    Edit::Replace { to, .. } => kept = Some(to),
+                    Edit::Delete { .. } => kept = None,
+                }
+            }
+            if let Some(char) = kept.filter(|_| column <= chars.len()) {
+                fixed.push(char);
+            }
+        }
+
+        let completion = match self.check(&fixed) {
+            Check::Incomplete { completion } => completion,
+            _ => String::new(),
+        };
+        fixed.push_str(&completion);
+        Repair { edits, completion, fixed }
+    }
+
+    // Works back through balanced[i][j] for the edits it chose.
+    fn trace(&self, tokens: &[(usize, char, Token)], balanced: &[Vec<usize>], i: usize, j: usize, end: usize, edits: &mut Vec<Edit>) {
+        if i == j {
+            return;
+        }
+        let (column, char, token) = tokens[i];
+        for k in i + 1..j {
+            let (other_column, other_char, other) = tokens[k];
+            let paired = match (token, other) {
+                (Token::Open(x), Token::Close(y)) if x == y => Some(vec![]),
+                (Token::Open(x), _) => Some(vec![Edit::Replace { column: other_column, from: other_char, to: self.delimiters[x].close }]),
+                (_, Token::Close(y)) => Some(vec![Edit::Replace { column, from: char, to: self.delimiters[y].open }]),
+                _ => None,
+            };
+            if let Some(paired) = paired {
+                if paired.len() + balanced[i + 1][k] + balanced[k + 1][j] == balanced[i][j] {
+                    edits.extend(paired);
+                    self.trace(tokens, balanced, i + 1, k, end, edits);
+                    self.trace(tokens, balanced, k + 1, j, end, edits);
+                    return;
+                }
+            }
+        }
+        // Pairing two tokens that are both wrong costs as much as dropping them both, so it's never
+        // needed. A lone opener gets its closer at the end of the stretch, anything else goes.
+        match token {
+            Token::Open(x) => {
+                let at = tokens.get(j).map_or(end + 1, |x| x.0);
+                edits.push(Edit::Insert { column: at, char: self.delimiters[x].close });
+            }
+            _ => edits.push(Edit::Delete { column, char }),
+        }
+        self.trace(tokens, balanced, i + 1, j, end, edits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    use crate::repair::Edit;
+    use crate::{Check, Syntax};
+
+    #[test]
+    fn example() {
+        let syntax = Syntax::default();
+        let repair = syntax.repair("{([(<{}[<>[]}>{[]{[(<()>");
+        assert_eq!(repair.edits, vec![Edit::Replace { column: 13, from: '}', to: ']' }]);
+        assert_eq!(repair.fixed, "{([(<{}[<>[]]>{[]{[(<()>)]}})])}");
+        assert_eq!(repair.edits[0].to_string(), "replace } with ] at column 13");
+
+        let repair = syntax.repair("[({(<(())[]>[[{[]{<()<>>");
+        assert_eq!((repair.edits, repair.completion.as_str()), (vec![], "}}]])})]"));
+
+        assert_eq!(syntax.repair(")()").edits, vec![Edit::Delete { column: 1, char: ')' }]);
+        assert_eq!(syntax.repair("(<)").fixed, "(<>)");
+        assert_eq!(syntax.repair("(a)").edits.len(), 1);
+        let lenient = Syntax { skip_unknown: true, ..Syntax::default() };
+        assert_eq!(lenient.repair("(a]").fixed, "(a)");
+    }
+
+    // Fewest edits for the line to be uncorrupted, trying every edit a step at a time.
+    fn brute_force(syntax: &Syntax, line: &str) -> usize {
+        let alphabet = syntax.delimiters().iter().flat_map(|x| [x.open, x.close]).collect::<Vec<char>>();
+        let mut level = HashSet::from([line.chars().collect::<Vec<char>>()]);
+        for distance in 0.. {
+            if level.iter().any(|x| !matches!(syntax.check(&x.iter().collect::<String>()), Check::Corrupted(_))) {
+                return distance;
+            }
+            let mut next = HashSet::new();
+            for chars in level {
+                for idx in 0..=chars.len() {
+                    for char in alphabet.iter().copied() {
+                        let mut inserted = chars.clone();
+                        inserted.insert(idx, char);
+                        next.insert(inserted);
+                        if idx < chars.len() {
+                            let mut replaced = chars.clone();
+                            replaced[idx] = char;
+                            next.insert(replaced);
+                        }
+                    }
+                    if idx < chars.len() {
+                        let mut deleted = chars.clone();
+                        deleted.remove(idx);
+                        next.insert(deleted);
+                    }
+                }
+            }
+            level = next;
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn minimal() {
+        let syntax = "( ) 3 1\n[ ] 57 2\n".parse::<Syntax>().unwrap();
+        let mut rng = StdRng::seed_from_u64(0xda942042e4dd58b5);
+        let chars = ['(', ')', '[', ']', 'x'];
+        for _ in 0..100 {
+            let line = (0..rng.gen_range(0..6)).map(|_| *chars.choose(&mut rng).unwrap()).collect::<String>();
+            let repair = syntax.repair(&line);
+            assert_eq!(repair.edits.len(), brute_force(&syntax, &line), "{}", line);
+            assert_eq!(syntax.check(&repair.fixed), Check::Complete, "{} -> {}", line, repair.fixed);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::str::FromStr;
+
+// Readings are packed into a u64, so that's as wide as they get.
+pub const MAX_WIDTH: usize = 64;
+
+// Decides which bit value to keep at a position, given how many readings have a 0 and a 1 there.
+pub trait BitCriteria {
+    fn select(&self, zeros: usize, ones: usize) -> bool;
+}
+
+impl<F> BitCriteria for F where F: Fn(usize, usize) -> bool {
+    fn select(&self, zeros: usize, ones: usize) -> bool {
+        self(zeros, ones)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criteria {
+    MostCommon { tie: bool },
+    LeastCommon { tie: bool },
+}
+
+impl Criteria {
+    // Part 1 settles a tie with a 0 in gamma, and so a 1 in epsilon.
+    pub const GAMMA: Criteria = Criteria::MostCommon { tie: false };
+    pub const EPSILON: Criteria = Criteria::LeastCommon { tie: true };
+    pub const OXYGEN: Criteria = Criteria::MostCommon { tie: true };
+    pub const CO2: Criteria = Criteria::LeastCommon { tie: false };
+
+    pub fn tie(&self) -> bool {
+        match *self {
+            Criteria::MostCommon { tie } | Criteria::LeastCommon { tie } => tie,
+        }
+    }
+}
+
+impl BitCriteria for Criteria {
+    fn select(&self, zeros: usize, ones: usize) -> bool {
+        match *self {
+            _ if zeros == ones => self.tie(),
+            Criteria::MostCommon { .. } => ones > zeros,
+            Criteria::LeastCommon { .. } => ones < zeros,
+        }
+    }
+}
+
+// A value built by a criteria, along with the bit positions (most significant first) where it had
+// to break a tie between equally many 0s and 1s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rating {
+    pub value: u64,
+    pub ties: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    width: usize,
+    readings: Vec<u64>,
+}
+
+pub fn parse_reading(s: &str) -> Result<u64, String> {
+    if s.is_empty() || s.len() > MAX_WIDTH {
+        return Err(format!("reading '{}' must be 1-{} bits wide", s, MAX_WIDTH));
+    }
+    let mut value = 0u64;
+    for (idx, char) in s.chars().enumerate() {
+        value = (value << 1) | match char {
+            '0' => 0,
+            '1' => 1,
+            _ => return Err(format!("unexpected char in reading '{}' at index {}: {}", s, idx, char)),
+        };
+    }
+    Ok(value)
+}
+
+impl FromStr for Diagnostics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).collect::<Vec<&str>>();
+        let width = match lines.first() {
+            Some(first) => first.len(),
+            None => return Err("no readings".to_string()),
+        };
+
+        let mut readings = Vec::with_capacity(lines.len());
+        for line in lines {
+            if line.len() != width {
+                return Err(format!("bad value {} not same length as others", line));
+            }
+            readings.push(parse_reading(line)?);
+        }
+        Ok(Diagnostics { width, readings })
+    }
+}
+
+impl Diagnostics {
+    pub fn new(width: usize, readings: Vec<u64>) -> Result<Diagnostics, String> {
+        if width == 0 || width > MAX_WIDTH {
+            return Err(format!("width must be 1-{} bits, got {}", MAX_WIDTH, width));
+        }
+        if readings.is_empty() {
+            return Err("no readings".to_string());
+        }
+        if let Some(bad) = readings.iter().find(|x| width < MAX_WIDTH && **x >> width != 0) {
+            return Err(format!("reading {:b} is wider than {} bits", bad, width));
+        }
+        Ok(Diagnostics { width, readings })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn readings(&self) -> &[u64] {
+        &self.readings
+    }
+
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (MAX_WIDTH - self.width)
+    }
+
+    // Number of set bits at each position, indexed from the least significant bit.
+    pub fn bit_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.width];
+        for reading in self.readings.iter() {
+            let mut v = *reading;
+            while v != 0 {
+                counts[v.trailing_zeros() as usize] += 1;
+                v &= v - 1;
+            }
+        }
+        counts
+    }
+
+    // Builds a value bit by bit, applying the criteria to the counts across every reading.
+    pub fn consensus<C>(&self, criteria: &C) -> Rating where C: BitCriteria {
+        let total = self.readings.len();
+        let mut rating = Rating { value: 0, ties: vec![] };
+        for (bit, ones) in self.bit_counts().iter().copied().enumerate().rev() {
+            let zeros = total - ones;
+            if zeros == ones {
+                rating.ties.push(bit);
+            }
+            if criteria.select(zeros, ones) {
+                rating.value |= 1 << bit;
+            }
+        }
+        rating
+    }
+
+    // Once sorted, every candidate left after filtering on the higher bits shares the same prefix, so
+    // the candidates are a contiguous range and each bit just splits it into its 0s and 1s.
+    pub fn filter<C>(&self, criteria: &C) -> Rating where C: BitCriteria {
+        let mut sorted = self.readings.clone();
+        sorted.sort_unstable();
+
+        let mut ties = vec![];
+        let (mut lo, mut hi) = (0, sorted.len());
+        for bit in (0..self.width).rev() {
+            if hi - lo == 1 {
+                break;
+            }
+            let split = lo + sorted[lo..hi].partition_point(|x| x >> bit & 1 == 0);
+            let (zeros, ones) = (split - lo, hi - split);
+            if zeros == 0 || ones == 0 {
+                continue;
+            }
+            if zeros == ones {
+                ties.push(bit);
+            }
+            if criteria.select(zeros, ones) {
+                lo = split;
+            } else {
+                hi = split;
+            }
+        }
+        Rating { value: sorted[lo], ties }
+    }
+
+    pub fn gamma(&self) -> u64 {
+        self.consensus(&Criteria::GAMMA).value
+    }
+
+    pub fn epsilon(&self) -> u64 {
+        self.consensus(&Criteria::EPSILON).value
+    }
+
+    // Bit positions where exactly half the readings are set.
+    pub fn tie_bits(&self) -> Vec<usize> {
+        self.consensus(&Criteria::GAMMA).ties
+    }
+
+    pub fn power_consumption(&self) -> u128 {
+        self.gamma() as u128 * self.epsilon() as u128
+    }
+
+    pub fn oxygen_rating(&self) -> u64 {
+        self.filter(&Criteria::OXYGEN).value
+    }
+
+    pub fn co2_rating(&self) -> u64 {
+        self.filter(&Criteria::CO2).value
+    }
+
+    pub fn life_support_rating(&self) -> u128 {
+        self.oxygen_rating() as u128 * self.co2_rating() as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_reading, Criteria, Diagnostics, Rating};
+
+    const EXAMPLE: &str = "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
+    #[test]
+    fn example_power() {
+        let diag = EXAMPLE.parse::<Diagnostics>().unwrap();
+        assert_eq!(diag.gamma(), 22);
+        assert_eq!(diag.epsilon(), 9);
+        assert_eq!(diag.power_consumption(), 198);
+    }
+
+    #[test]
+    fn example_life_support() {
+        let diag = EXAMPLE.parse::<Diagnostics>().unwrap();
+        assert_eq!(diag.oxygen_rating(), 23);
+        assert_eq!(diag.co2_rating(), 10);
+        assert_eq!(diag.life_support_rating(), 230);
+    }
+
+    #[test]
+    fn wide_readings() {
+        let a = "1".repeat(64);
+        let b = format!("0{}", "1".repeat(63));
+        let diag = format!("{}\n{}\n{}\n", a, a, b).parse::<Diagnostics>().unwrap();
+        assert_eq!(diag.gamma(), u64::MAX);
+        assert_eq!(diag.epsilon(), 0);
+        assert_eq!(diag.oxygen_rating(), u64::MAX);
+        assert_eq!(diag.co2_rating(), u64::MAX >> 1);
+    }
+
+    #[test]
+    fn ties() {
+        let diag = "1100\n1010\n0001\n".parse::<Diagnostics>().unwrap();
+        assert_eq!(diag.tie_bits(), vec![]);
+        assert_eq!(diag.filter(&Criteria::OXYGEN), Rating { value: 0b1100, ties: vec![2] });
+        assert_eq!(diag.filter(&Criteria::MostCommon { tie: false }), Rating { value: 0b1010, ties: vec![2] });
+        assert_eq!(diag.filter(&Criteria::CO2), Rating { value: 0b0001, ties: vec![] });
+
+        let diag = "10\n01\n".parse::<Diagnostics>().unwrap();
+        assert_eq!(diag.tie_bits(), vec![1, 0]);
+        assert_eq!(diag.gamma(), 0b00);
+        assert_eq!(diag.epsilon(), 0b11);
+        assert_eq!(diag.consensus(&Criteria::MostCommon { tie: true }).value, 0b11);
+    }
+
+    #[test]
+    fn custom_criteria() {
+        let diag = "100\n101\n110\n111\n011\n".parse::<Diagnostics>().unwrap();
+        // Always keep the 0s, so the smallest reading survives.
+        assert_eq!(diag.filter(&|_zeros, _ones| false).value, 0b011);
+        assert_eq!(diag.consensus(&|zeros: usize, ones: usize| ones >= 2 * zeros).value, 0b100);
+    }
+
+    #[test]
+    fn bad_readings() {
+        assert!(parse_reading("10a1").is_err());
+        assert!(parse_reading(&"1".repeat(65)).is_err());
+        assert!("101\n10\n".parse::<Diagnostics>().is_err());
+        assert!("".parse::<Diagnostics>().is_err());
+    }
+}
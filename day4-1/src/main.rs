@@ -1,87 +1,40 @@
-use std::io::BufRead;
+use std::io::Read;
 
-#[derive(Debug)]
-struct BingoBoard([Option<u8>; 25]);
+use day4::{odds, Game, Rules, Session};
 
-impl BingoBoard {
-    fn row(&self, y: usize) -> &[Option<u8>] {
-        &self.0[y * 5..y*5+5]
-    }
+fn main() -> Result<(), String> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).map_err(|e| e.to_string())?;
+    let game = input.parse::<Game>()?;
+    let rules = Rules { diagonals: std::env::args().any(|x| x == "--diagonals") };
 
-    fn col(&self, x: usize) -> [Option<u8>; 5] {
-        let mut col = [None; 5];
-        let mut pos = x;
-        for i in 0..5 {
-            col[i] = self.0[pos];
-            pos += 5;
-        }
-        col
+    println!("bingo_numbers {:?}", game.draws);
+
+    match game.play(&rules).first() {
+        Some(win) => println!("Bingo! {}", win.score),
+        None => println!("No bingo."),
     }
 
-    fn bingo(&self) -> bool {
-        for y in 0..5 {
-            if self.row(y).iter().all(|x| x.is_none()) {
-                return true;
-            }
-        }
+    let session = Session::new(&game.boards, &rules);
 
-        for x in 0..5 {
-            if self.col(x).iter().all(|x| x.is_none()) {
-                return true;
-            }
+    if let Some(trials) = args::value::<usize>("odds")? {
+        let seed = args::value("seed")?.unwrap_or(0);
+        for (idx, board) in odds::estimate(&session, &game.draws, trials, seed).iter().enumerate() {
+            let (first_lo, first_hi) = board.first.interval(1.96);
+            let (last_lo, last_hi) = board.last.interval(1.96);
+            println!("board {}: first {:.4} [{:.4}, {:.4}] last {:.4} [{:.4}, {:.4}]",
+                idx, board.first.estimate(), first_lo, first_hi, board.last.estimate(), last_lo, last_hi);
         }
-
-        return false;
     }
 
-    fn remove(&mut self, num: u8) {
-        let mut remove_idx = vec![];
-        for (idx, board_num) in self.0.iter().enumerate() {
-            if Some(num) == *board_num {
-                remove_idx.push(idx);
-            }
-        }
-        for idx in remove_idx {
-            self.0[idx] = None;
+    if let Some(board) = args::value::<usize>("fastest")? {
+        if board >= session.board_count() {
+            return Err(format!("--fastest: there are only {} boards", session.board_count()));
         }
-    }
-}
-
-fn main() {
-    let stdin = std::io::stdin();
-    let mut stdin = stdin.lock().lines();
-
-    let bingo_numbers = stdin.next()
-        .expect("bingo numbers").expect("bingo numbers")
-        .split(",")
-        .map(|x| x.parse::<u8>().expect("parse bingo number"))
-        .collect::<Vec<u8>>();
-
-    println!("bingo_numbers {:?}", bingo_numbers);
-
-    let mut boards = Vec::new();
-    while let Some(empty) = stdin.next() {
-        assert_eq!(empty.unwrap().len(), 0);
-        let board = BingoBoard((0..5).flat_map(|y| {
-            stdin.next().expect("bingo board row").unwrap()
-                .split_whitespace()
-                .map(|x| Some(x.parse::<u8>().expect("bingo board number")))
-                .collect::<Vec<Option<u8>>>()
-        }).collect::<Vec<Option<u8>>>().try_into().unwrap());
-        boards.push(board);
-    }
-
-    for num in bingo_numbers {
-        for mut board in boards.iter_mut() {
-            board.remove(num);
-            if board.bingo() {
-                let sum = board.0.iter()
-                    .filter(|x| x.is_some())
-                    .map(|x| x.unwrap() as u32)
-                    .sum::<u32>();
-                println!("Bingo! {}", sum * (num as u32));
-                return;
-            }
+        match session.fastest_win(board) {
+            Some(draws) => println!("board {} wins first after {} draws: {:?}", board, draws.len(), draws),
+            None => println!("board {} can never win first", board),
         }
     }
+    Ok(())
 }
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub mod odds;
+
+pub type Number = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BingoBoard {
+    width: usize,
+    height: usize,
+    // Cells are None once their number has been drawn.
+    cells: Vec<Option<Number>>,
+}
+
+impl BingoBoard {
+    pub fn new(width: usize, height: usize, numbers: Vec<Number>) -> Result<BingoBoard, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("bingo board must not be empty, got {}x{}", width, height));
+        }
+        if numbers.len() != width * height {
+            return Err(format!("{}x{} bingo board needs {} numbers, got {}", width, height, width * height, numbers.len()));
+        }
+        Ok(BingoBoard { width, height, cells: numbers.into_iter().map(Some).collect() })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[Option<Number>] {
+        &self.cells
+    }
+
+    pub fn row(&self, y: usize) -> &[Option<Number>] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn col(&self, x: usize) -> impl Iterator<Item=&Option<Number>> {
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    // Diagonals only exist on square boards.
+    pub fn diagonals(&self) -> Vec<Vec<usize>> {
+        if self.width != self.height {
+            return vec![];
+        }
+        let n = self.width;
+        vec![
+            (0..n).map(|i| i * n + i).collect(),
+            (0..n).map(|i| i * n + (n - 1 - i)).collect(),
+        ]
+    }
+
+    pub fn bingo(&self, rules: &Rules) -> bool {
+        if (0..self.height).any(|y| self.row(y).iter().all(|x| x.is_none())) {
+            return true;
+        }
+        if (0..self.width).any(|x| self.col(x).all(|x| x.is_none())) {
+            return true;
+        }
+        rules.diagonals && self.diagonals().iter().any(|diag| diag.iter().all(|idx| self.cells[*idx].is_none()))
+    }
+
+    pub fn remove(&mut self, num: Number) {
+        for cell in self.cells.iter_mut() {
+            if *cell == Some(num) {
+                *cell = None;
+            }
+        }
+    }
+
+    pub fn unmarked_sum(&self) -> u128 {
+        self.cells.iter().flatten().map(|x| *x as u128).sum()
+    }
+}
+
+impl FromStr for BingoBoard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut width = 0;
+        let mut numbers = vec![];
+        let mut height = 0;
+        for line in s.lines().filter(|x| !x.trim().is_empty()) {
+            let row = line.split_whitespace()
+                .map(|x| x.parse::<Number>().map_err(|e| format!("bingo board number '{}': {}", x, e)))
+                .collect::<Result<Vec<Number>, String>>()?;
+            if height == 0 {
+                width = row.len();
+            } else if row.len() != width {
+                return Err(format!("bingo board row '{}' has {} numbers, expected {}", line, row.len(), width));
+            }
+            numbers.extend(row);
+            height += 1;
+        }
+        BingoBoard::new(width, height, numbers)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub diagonals: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    pub board: usize,
+    // Index into the draw sequence of the number that completed the board.
+    pub turn: usize,
+    pub number: Number,
+    pub score: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    // One entry per board, None for boards that never won.
+    pub wins: Vec<Option<Win>>,
+}
+
+impl Outcome {
+    pub fn first(&self) -> Option<Win> {
+        self.wins.iter().flatten().min_by_key(|x| (x.turn, x.board)).copied()
+    }
+
+    pub fn last(&self) -> Option<Win> {
+        self.wins.iter().flatten().max_by_key(|x| (x.turn, x.board)).copied()
+    }
+
+    // Every win in the order it happened.
+    pub fn ordered(&self) -> Vec<Win> {
+        let mut wins = self.wins.iter().flatten().copied().collect::<Vec<Win>>();
+        wins.sort_by_key(|x| (x.turn, x.board));
+        wins
+    }
+}
+
+pub fn play(boards: &[BingoBoard], draws: &[Number], rules: &Rules) -> Outcome {
+    Session::new(boards, rules).play(draws)
+}
+
+// Everything needed to replay a set of boards against any number of draw sequences. Each drawn number
+// is looked up in a reverse index, and every board row, column and diagonal keeps a count of its
+// unmarked cells, so a whole game costs one visit per matching cell rather than a rescan per draw.
+#[derive(Debug, Clone)]
+pub struct Session {
+    positions: HashMap<Number, Vec<(usize, usize)>>,
+    boards: Vec<SessionBoard>,
+    // Unmarked cells left in each board line before anything is drawn.
+    remaining: Vec<usize>,
+    // Boards that came in with a line already marked off, which win on the first draw.
+    complete: Vec<usize>,
+    numbers: Vec<Option<Number>>,
+}
+
+#[derive(Debug, Clone)]
+struct SessionBoard {
+    width: usize,
+    height: usize,
+    diagonals: bool,
+    cell_offset: usize,
+    line_offset: usize,
+    unmarked_sum: u128,
+}
+
+impl SessionBoard {
+    // Offsets (relative to line_offset) of the rows, column and diagonals running through a cell.
+    fn lines(&self, idx: usize) -> impl Iterator<Item=usize> {
+        let (x, y) = (idx % self.width, idx / self.width);
+        let diag_base = self.height + self.width;
+        [
+            Some(y),
+            Some(self.height + x),
+            if self.diagonals && x == y { Some(diag_base) } else { None },
+            if self.diagonals && x + y == self.width - 1 { Some(diag_base + 1) } else { None },
+        ].into_iter().flatten()
+    }
+
+    fn line_count(&self) -> usize {
+        self.height + self.width + if self.diagonals { 2 } else { 0 }
+    }
+}
+
+impl Session {
+    pub fn new(boards: &[BingoBoard], rules: &Rules) -> Session {
+        let mut positions: HashMap<Number, Vec<(usize, usize)>> = HashMap::new();
+        let mut session_boards = Vec::with_capacity(boards.len());
+        let mut remaining = vec![];
+        let mut complete = vec![];
+        let mut numbers = vec![];
+
+        for (board_idx, board) in boards.iter().enumerate() {
+            let session_board = SessionBoard {
+                width: board.width,
+                height: board.height,
+                diagonals: rules.diagonals && board.width == board.height,
+                cell_offset: numbers.len(),
+                line_offset: remaining.len(),
+                unmarked_sum: board.unmarked_sum(),
+            };
+            remaining.resize(remaining.len() + session_board.line_count(), 0);
+            for (cell, num) in board.cells.iter().enumerate() {
+                if let Some(num) = num {
+                    positions.entry(*num).or_default().push((board_idx, cell));
+                    for line in session_board.lines(cell) {
+                        remaining[session_board.line_offset + line] += 1;
+                    }
+                }
+            }
+            if remaining[session_board.line_offset..].contains(&0) {
+                complete.push(board_idx);
+            }
+            numbers.extend(board.cells.iter().copied());
+            session_boards.push(session_board);
+        }
+
+        Session { positions, boards: session_boards, remaining, complete, numbers }
+    }
+
+    pub fn board_count(&self) -> usize {
+        self.boards.len()
+    }
+
+    // Where a number appears, as (board, cell) pairs.
+    pub fn positions(&self, num: Number) -> &[(usize, usize)] {
+        self.positions.get(&num).map(|x| x.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn play(&self, draws: &[Number]) -> Outcome {
+        let mut marked = vec![false; self.numbers.len()];
+        let mut remaining = self.remaining.clone();
+        let mut sums = self.boards.iter().map(|x| x.unmarked_sum).collect::<Vec<u128>>();
+        let mut wins = vec![None; self.boards.len()];
+        let mut won_count = 0;
+
+        for (turn, num) in draws.iter().copied().enumerate() {
+            if won_count == self.boards.len() {
+                break;
+            }
+            let mut completed = if turn == 0 { self.complete.clone() } else { vec![] };
+            for (board_idx, cell) in self.positions(num).iter().copied() {
+                if wins[board_idx].is_some() {
+                    continue;
+                }
+                let board = &self.boards[board_idx];
+                if std::mem::replace(&mut marked[board.cell_offset + cell], true) {
+                    continue;
+                }
+                sums[board_idx] -= num as u128;
+                for line in board.lines(cell) {
+                    remaining[board.line_offset + line] -= 1;
+                    if remaining[board.line_offset + line] == 0 && completed.last() != Some(&board_idx) {
+                        completed.push(board_idx);
+                    }
+                }
+            }
+            // Scores only get tallied once the number has been marked everywhere it appears.
+            for board_idx in completed {
+                if wins[board_idx].is_none() {
+                    wins[board_idx] = Some(Win { board: board_idx, turn, number: num, score: sums[board_idx] * num as u128 });
+                    won_count += 1;
+                }
+            }
+        }
+
+        Outcome { wins }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub draws: Vec<Number>,
+    pub boards: Vec<BingoBoard>,
+}
+
+impl Game {
+    pub fn play(&self, rules: &Rules) -> Outcome {
+        play(&self.boards, &self.draws, rules)
+    }
+}
+
+impl FromStr for Game {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut blocks = s.split("\n\n");
+
+        let draws = blocks.next().ok_or("missing bingo numbers")?
+            .trim()
+            .split(',')
+            .map(|x| x.trim().parse::<Number>().map_err(|e| format!("bingo number '{}': {}", x, e)))
+            .collect::<Result<Vec<Number>, String>>()?;
+
+        let boards = blocks
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.parse::<BingoBoard>())
+            .collect::<Result<Vec<BingoBoard>, String>>()?;
+
+        Ok(Game { draws, boards })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::{BingoBoard, Game, Rules};
+
+    const EXAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+    #[test]
+    fn example() {
+        let game = EXAMPLE.parse::<Game>().unwrap();
+        let outcome = game.play(&Rules::default());
+        let first = outcome.first().unwrap();
+        assert_eq!((first.board, first.number, first.score), (2, 24, 4512));
+        let last = outcome.last().unwrap();
+        assert_eq!((last.board, last.number, last.score), (1, 13, 1924));
+    }
+
+    #[test]
+    fn rectangular_board_and_diagonals() {
+        let board = "1 2 3\n4 5 6\n".parse::<BingoBoard>().unwrap();
+        assert_eq!((board.width(), board.height()), (3, 2));
+        let outcome = crate::play(&[board], &[1, 5, 3, 6], &Rules { diagonals: true });
+        assert_eq!(outcome.first().unwrap().turn, 3);
+
+        let board = "1 2\n3 4\n".parse::<BingoBoard>().unwrap();
+        let boards = [board];
+        let outcome = crate::play(&boards, &[1, 4, 2], &Rules { diagonals: true });
+        assert_eq!(outcome.first().unwrap().turn, 1);
+        let outcome = crate::play(&boards, &[1, 4, 2], &Rules::default());
+        assert_eq!(outcome.first().unwrap().turn, 2);
+    }
+
+    // The straightforward way to play: mark every board on every draw and rescan it for a bingo.
+    fn play_naive(boards: &[BingoBoard], draws: &[u64], rules: &Rules) -> Vec<Option<(usize, u128)>> {
+        let mut boards = boards.to_vec();
+        let mut wins = vec![None; boards.len()];
+        for (turn, num) in draws.iter().copied().enumerate() {
+            for (idx, board) in boards.iter_mut().enumerate() {
+                if wins[idx].is_some() {
+                    continue;
+                }
+                board.remove(num);
+                if board.bingo(rules) {
+                    wins[idx] = Some((turn, board.unmarked_sum() * num as u128));
+                }
+            }
+        }
+        wins
+    }
+
+    #[test]
+    fn indexed_matches_naive() {
+        // Small numbers on small boards, so duplicates within a board and across draws are common.
+        let mut rng = StdRng::seed_from_u64(0x2545f4914f6cdd1d);
+        let mut rand = |n: u64| rng.gen_range(0..n);
+        for _ in 0..200 {
+            let (w, h) = (1 + rand(4) as usize, 1 + rand(4) as usize);
+            let boards = (0..1 + rand(6)).map(|_| {
+                let mut board = BingoBoard::new(w, h, (0..w * h).map(|_| rand(12)).collect()).unwrap();
+                if rand(4) == 0 {
+                    board.remove(rand(12));
+                }
+                board
+            }).collect::<Vec<BingoBoard>>();
+            let draws = (0..rand(20)).map(|_| rand(14)).collect::<Vec<u64>>();
+            let rules = Rules { diagonals: rand(2) == 0 };
+
+            let outcome = crate::play(&boards, &draws, &rules);
+            let expected = play_naive(&boards, &draws, &rules);
+            assert_eq!(outcome.wins.iter().map(|x| x.map(|x| (x.turn, x.score))).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn bad_board() {
+        assert!("1 2 3\n4 5\n".parse::<BingoBoard>().is_err());
+        assert!("1 x\n".parse::<BingoBoard>().is_err());
+    }
+}
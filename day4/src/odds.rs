@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::{Number, Session};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Proportion {
+    pub hits: usize,
+    pub trials: usize,
+}
+
+impl Proportion {
+    pub fn estimate(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.trials as f64
+    }
+
+    // Wilson score interval, which behaves itself for the long shots that never win a single trial.
+    // z is the normal quantile, e.g. 1.96 for 95% confidence.
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        if self.trials == 0 {
+            return (0.0, 1.0);
+        }
+        let n = self.trials as f64;
+        let p = self.estimate();
+        let denominator = 1.0 + z * z / n;
+        let centre = (p + z * z / (2.0 * n)) / denominator;
+        let spread = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+        ((centre - spread).max(0.0), (centre + spread).min(1.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoardOdds {
+    pub first: Proportion,
+    pub last: Proportion,
+}
+
+// Plays `trials` games, each drawing the whole pool in a freshly shuffled order, and tallies how often
+// each board wins first and last. Boards winning on the same turn are split the same way as
+// Outcome::first and Outcome::last do it, by board index.
+pub fn estimate(session: &Session, pool: &[Number], trials: usize, seed: u64) -> Vec<BoardOdds> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut odds = vec![BoardOdds::default(); session.board_count()];
+    let mut draws = pool.to_vec();
+
+    for _ in 0..trials {
+        draws.shuffle(&mut rng);
+        let outcome = session.play(&draws);
+        for board in odds.iter_mut() {
+            board.first.trials += 1;
+            board.last.trials += 1;
+        }
+        if let Some(win) = outcome.first() {
+            odds[win.board].first.hits += 1;
+        }
+        if let Some(win) = outcome.last() {
+            odds[win.board].last.hits += 1;
+        }
+    }
+    odds
+}
+
+impl Session {
+    // The shortest draw order that makes `board` the first winner, or None if no order can.
+    //
+    // Any winning order has drawn every number of at least one line of the board, and dropping the
+    // other numbers can only hold the other boards back, so it's enough to try each line's numbers
+    // alone, shortest line first. The only question then is which number goes last: any other board
+    // line made up entirely of this line's numbers has to be missing it, or it wins first (boards
+    // ahead of this one in the list also win a tie, so they can't be made up of those numbers at all).
+    pub fn fastest_win(&self, board_idx: usize) -> Option<Vec<Number>> {
+        let board = &self.boards[board_idx];
+
+        let mut lines = vec![HashSet::new(); board.line_count()];
+        for idx in 0..board.width * board.height {
+            if let Some(num) = self.numbers[board.cell_offset + idx] {
+                for line in board.lines(idx) {
+                    lines[line].insert(num);
+                }
+            }
+        }
+        let mut lines = lines.into_iter()
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                let mut nums = x.into_iter().collect::<Vec<Number>>();
+                nums.sort_unstable();
+                nums
+            })
+            .collect::<Vec<Vec<Number>>>();
+        lines.sort_by_key(|x| x.len());
+
+        'lines: for nums in lines {
+            // Other board lines this draw would fill in, and which of its numbers they contain.
+            let mut covered: HashMap<(usize, usize), Vec<Number>> = HashMap::new();
+            for num in nums.iter().copied() {
+                for (other_idx, cell) in self.positions(num).iter().copied() {
+                    if other_idx == board_idx {
+                        continue;
+                    }
+                    let other = &self.boards[other_idx];
+                    for line in other.lines(cell) {
+                        covered.entry((other_idx, other.line_offset + line)).or_default().push(num);
+                    }
+                }
+            }
+
+            let mut last_candidates = nums.iter().copied().collect::<HashSet<Number>>();
+            for ((other_idx, line), line_nums) in covered {
+                if line_nums.len() < self.remaining[line] {
+                    continue;
+                }
+                if other_idx < board_idx {
+                    continue 'lines;
+                }
+                last_candidates.retain(|x| line_nums.contains(x));
+            }
+
+            if let Some(last) = last_candidates.into_iter().min() {
+                let mut draws = nums.into_iter().filter(|x| *x != last).collect::<Vec<Number>>();
+                draws.push(last);
+                // Boards that came in with a line already complete can still get in the way.
+                if self.play(&draws).first().map(|x| x.board) == Some(board_idx) {
+                    return Some(draws);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::odds::{estimate, Proportion};
+    use crate::{BingoBoard, Rules, Session};
+
+    #[test]
+    fn interval() {
+        let p = Proportion { hits: 50, trials: 100 };
+        let (lo, hi) = p.interval(1.96);
+        assert!((lo - 0.4038).abs() < 1e-3 && (hi - 0.5962).abs() < 1e-3);
+        let (lo, hi) = Proportion { hits: 0, trials: 10 }.interval(1.96);
+        assert_eq!(lo, 0.0);
+        assert!(hi > 0.0 && hi < 0.5);
+    }
+
+    #[test]
+    fn symmetric_boards() {
+        let boards = ["1 2\n3 4\n", "5 6\n7 8\n"].iter()
+            .map(|x| x.parse::<BingoBoard>().unwrap())
+            .collect::<Vec<BingoBoard>>();
+        let session = Session::new(&boards, &Rules::default());
+        let odds = estimate(&session, &(1..=8).collect::<Vec<u64>>(), 2000, 42);
+        assert_eq!(odds, estimate(&session, &(1..=8).collect::<Vec<u64>>(), 2000, 42));
+        for board in odds.iter() {
+            let (lo, hi) = board.first.interval(3.0);
+            assert!(lo < 0.5 && hi > 0.5);
+        }
+        assert_eq!(odds[0].first.hits + odds[1].first.hits, 2000);
+    }
+
+    #[test]
+    fn fastest_win() {
+        let boards = ["1 2 3\n4 5 6\n7 8 9\n", "1 2\n10 11\n", "4 5\n6 12\n"].iter()
+            .map(|x| x.parse::<BingoBoard>().unwrap())
+            .collect::<Vec<BingoBoard>>();
+        let session = Session::new(&boards, &Rules::default());
+
+        // Board 1 fills its 1 2 row along the way, so 1 has to come last for board 0 to take the tie.
+        let draws = session.fastest_win(0).unwrap();
+        assert_eq!(draws, vec![2, 3, 1]);
+        assert_eq!(session.play(&draws).first().unwrap().board, 0);
+
+        assert_eq!(session.fastest_win(1).unwrap(), vec![2, 1]);
+        assert_eq!(session.fastest_win(2).unwrap(), vec![5, 4]);
+
+        // 4 5 6 fills two of board 2's lines, which both need the 4.
+        let board = "4 5 6\n7 8 9\n10 11 13\n".parse::<BingoBoard>().unwrap();
+        let session = Session::new(&[board, boards[2].clone()], &Rules::default());
+        assert_eq!(session.fastest_win(0).unwrap(), vec![5, 6, 4]);
+
+        // A copy of an earlier board can never come first.
+        let session = Session::new(&[boards[1].clone(), boards[1].clone()], &Rules::default());
+        assert_eq!(session.fastest_win(1), None);
+    }
+}
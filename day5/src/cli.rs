@@ -0,0 +1,41 @@
+use std::io::Read;
+
+use crate::{parse_segments, Coord, Filter, VentField};
+
+// day5-1 and day5-2 both run this, with their own filter for when there's no --filter. Commands:
+//
+//   count              number of overlapping points (or points under --at-least=K segments)
+//   covering X,Y[,Z]   segments covering a point
+//   points             every overlapping point with its coverage
+//   densest            the point covered by the most segments
+pub fn main(default_filter: Filter) -> Result<(), String> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).map_err(|e| e.to_string())?;
+    let segments = parse_segments(&input)?;
+
+    let filter = args::value("filter")?.unwrap_or(default_filter);
+    let at_least = args::value("at-least")?.unwrap_or(2);
+    let field = VentField::new(&segments, filter);
+
+    let args = std::env::args().skip(1).filter(|x| !x.starts_with("--")).collect::<Vec<String>>();
+    match args.iter().map(|x| x.as_str()).collect::<Vec<&str>>()[..] {
+        [] | ["count"] => println!("Total overlaps: {}", field.count_at_least(at_least)),
+        ["covering", c] => {
+            let c = c.parse::<Coord>()?;
+            for idx in field.covering(c) {
+                println!("line {}: {}", idx + 1, field.segments()[idx]);
+            }
+        }
+        ["points"] => {
+            for (c, count) in field.points_at_least(at_least) {
+                println!("{} {}", c.format(field.three_d()), count);
+            }
+        }
+        ["densest"] => match field.densest() {
+            Some((c, count)) => println!("{} is covered by {} segments", c.format(field.three_d()), count),
+            None => println!("No segments."),
+        },
+        _ => return Err(format!("unknown command {:?}, expected count, covering X,Y[,Z], points or densest", args)),
+    }
+    Ok(())
+}
@@ -0,0 +1,618 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+
+pub mod cli;
+
+// Vents in the plane just sit at z = 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coord {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl Coord {
+    pub fn new(x: i64, y: i64) -> Coord {
+        Coord { x, y, z: 0 }
+    }
+
+    fn axes(&self) -> [i64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    // x,y,z in 3D, or x,y. There's no telling which from the coord itself, as vents in the plane sit at
+    // z = 0 and so can vents in space.
+    pub fn format(&self, three_d: bool) -> String {
+        match three_d {
+            true => format!("{},{},{}", self.x, self.y, self.z),
+            false => format!("{},{}", self.x, self.y),
+        }
+    }
+
+    fn from_axes(axes: [i128; 3]) -> Option<Coord> {
+        Some(Coord {
+            x: i64::try_from(axes[0]).ok()?,
+            y: i64::try_from(axes[1]).ok()?,
+            z: i64::try_from(axes[2]).ok()?,
+        })
+    }
+}
+
+impl FromStr for Coord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coords = s
+            .split(',')
+            .map(|x| x.trim().parse::<i64>().map_err(|e| format!("coord '{}': {}", s, e)))
+            .collect::<Result<Vec<i64>, String>>()?;
+        match coords[..] {
+            [x, y] => Ok(Coord { x, y, z: 0 }),
+            [x, y, z] => Ok(Coord { x, y, z }),
+            _ => Err(format!("coord '{}' should be x,y or x,y,z", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineSegment {
+    pub c1: Coord,
+    pub c2: Coord,
+    // Whether the segment was written as x,y,z rather than x,y, so it's printed back the same way.
+    pub three_d: bool,
+}
+
+impl FromStr for LineSegment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut it = s.split(" -> ");
+        let (c1, c2) = match (it.next(), it.next(), it.next()) {
+            (Some(c1), Some(c2), None) => (c1, c2),
+            _ => return Err(format!("line segment '{}' should be x1,y1 -> x2,y2 or x1,y1,z1 -> x2,y2,z2", s)),
+        };
+        let three_d = c1.split(',').count() == 3;
+        if three_d != (c2.split(',').count() == 3) {
+            return Err(format!("line segment '{}' mixes 2D and 3D endpoints", s));
+        }
+        Ok(LineSegment { c1: c1.parse()?, c2: c2.parse()?, three_d })
+    }
+}
+
+impl Display for LineSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}", self.c1.format(self.three_d), self.c2.format(self.three_d))
+    }
+}
+
+pub fn parse_segments(s: &str) -> Result<Vec<LineSegment>, String> {
+    s.lines()
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| x.parse::<LineSegment>())
+        .collect()
+}
+
+impl LineSegment {
+    fn delta(&self) -> [i128; 3] {
+        let (c1, c2) = (self.c1.axes(), self.c2.axes());
+        [0, 1, 2].map(|i| c2[i] as i128 - c1[i] as i128)
+    }
+
+    // Moves along at most one axis.
+    pub fn is_axis_aligned(&self) -> bool {
+        self.delta().iter().filter(|x| **x != 0).count() <= 1
+    }
+
+    // Moves the same distance along every axis it moves along at all, and there's more than one.
+    pub fn is_diagonal(&self) -> bool {
+        let moved = self.delta().into_iter().filter(|x| *x != 0).map(|x| x.abs()).collect::<Vec<i128>>();
+        moved.len() > 1 && moved.iter().all(|x| *x == moved[0])
+    }
+
+    // How far to move from c1 each step towards c2, so that every lattice point on the segment gets
+    // visited. Along one axis that can be as much as 2^64 - 1.
+    pub fn step(&self) -> [i128; 3] {
+        let delta = self.delta();
+        let divisor = delta.iter().fold(0, |acc, x| gcd(acc, x.abs())).max(1);
+        delta.map(|x| x / divisor)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item=Coord> {
+        let (step, c1) = (self.step(), self.c1.axes().map(|x| x as i128));
+        (0..=self.steps()).map(move |i| Coord::from_axes([0, 1, 2].map(|k| c1[k] + i * step[k])).expect("point on segment"))
+    }
+
+    fn steps(&self) -> i128 {
+        let (step, delta) = (self.step(), self.delta());
+        (0..3).find(|i| step[*i] != 0).map(|i| delta[i] / step[i]).unwrap_or(0)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    AxisAligned,
+    // Axis-aligned and 45 degree segments.
+    Diagonal,
+    All,
+}
+
+impl Filter {
+    pub fn allows(&self, seg: &LineSegment) -> bool {
+        match self {
+            Filter::AxisAligned => seg.is_axis_aligned(),
+            Filter::Diagonal => seg.is_axis_aligned() || seg.is_diagonal(),
+            Filter::All => true,
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "axis" => Ok(Filter::AxisAligned),
+            "diagonal" => Ok(Filter::Diagonal),
+            "all" => Ok(Filter::All),
+            _ => Err(format!("unknown filter '{}', expected axis, diagonal or all", s)),
+        }
+    }
+}
+
+// An infinite line of lattice points, identified by its direction and its anchor: the lattice point
+// whose coordinate along the direction's longest axis falls in [0, that axis's step). Positions along
+// the line count steps from the anchor. Directions are normalised so that their first non-zero axis
+// is positive. Anchoring on the longest axis keeps anchors, and so everything worked out from them,
+// within a few times 2^64 even for the steepest lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Line {
+    dir: [i128; 3],
+    anchor: [i128; 3],
+}
+
+impl Line {
+    fn through(c: Coord, dir: [i128; 3]) -> Line {
+        let dir = match dir.iter().find(|x| **x != 0) {
+            Some(first) if *first < 0 => dir.map(|x| -x),
+            _ => dir,
+        };
+        let axis = Line::longest(dir);
+        let c = c.axes().map(|x| x as i128);
+        let steps = c[axis].div_euclid(dir[axis]);
+        Line { dir, anchor: [0, 1, 2].map(|i| c[i] - steps * dir[i]) }
+    }
+
+    fn longest(dir: [i128; 3]) -> usize {
+        (0..3).rev().max_by_key(|i| dir[*i].abs()).unwrap()
+    }
+
+    // Steps from the anchor to a point on the line.
+    fn position(&self, c: Coord) -> i128 {
+        let axis = Line::longest(self.dir);
+        (c.axes()[axis] as i128 - self.anchor[axis]) / self.dir[axis]
+    }
+
+    fn at(&self, position: i128) -> Coord {
+        Coord::from_axes([0, 1, 2].map(|i| self.anchor[i] + position * self.dir[i])).expect("point on line")
+    }
+
+    // Products of steps can outgrow i128, so lines with long steps are intersected with big integers.
+    fn intersection(&self, other: &Line) -> Option<Coord> {
+        let short = |x: &Line| x.dir.iter().all(|x| x.abs() <= 1 << 40);
+        match short(self) && short(other) {
+            true => intersect::<i128>(self, other),
+            false => intersect::<BigInt>(self, other),
+        }
+    }
+}
+
+// Solves a.anchor + s * a.dir = b.anchor + t * b.dir in whichever plane the two directions aren't
+// parallel in, and then checks the solution holds in 3D too.
+fn intersect<T>(a: &Line, b: &Line) -> Option<Coord>
+where
+    T: Clone + PartialOrd + From<i128> + Add<Output=T> + Sub<Output=T> + Mul<Output=T> + Div<Output=T> + Rem<Output=T>,
+    i64: TryFrom<T>,
+{
+    let (da, db) = (a.dir.map(T::from), b.dir.map(T::from));
+    let offset = [0, 1, 2].map(|i| T::from(b.anchor[i] - a.anchor[i]));
+    let zero = T::from(0);
+    let (i, j, det) = [(0, 1), (1, 2), (0, 2)].into_iter()
+        .map(|(i, j)| (i, j, da[i].clone() * db[j].clone() - da[j].clone() * db[i].clone()))
+        .find(|x| x.2 != zero)?;
+    let s = offset[i].clone() * db[j].clone() - offset[j].clone() * db[i].clone();
+    let t = offset[i].clone() * da[j].clone() - offset[j].clone() * da[i].clone();
+    if s.clone() % det.clone() != zero || t.clone() % det.clone() != zero {
+        return None;
+    }
+    let (s, t) = (s / det.clone(), t / det);
+    // Further than 2^64 steps from an anchor is outside any i64 coordinate.
+    let far = T::from(1 << 64);
+    if [&s, &t].iter().any(|x| **x > far || **x < zero.clone() - far.clone()) {
+        return None;
+    }
+    let p = [0, 1, 2].map(|k| T::from(a.anchor[k]) + s.clone() * da[k].clone());
+    if (0..3).any(|k| p[k] != T::from(b.anchor[k]) + t.clone() * db[k].clone()) {
+        return None;
+    }
+    let [x, y, z] = p;
+    Some(Coord { x: i64::try_from(x).ok()?, y: i64::try_from(y).ok()?, z: i64::try_from(z).ok()? })
+}
+
+// A run of positions [start, end) along a line that's covered by the same number of segments.
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    start: i128,
+    end: i128,
+    count: u64,
+}
+
+// Sparse coverage of the vent field. Segments are never walked point by point: overlapping segments
+// on the same line get swept into pieces, and the only points looked at individually are where lines
+// running in different directions cross.
+#[derive(Debug, Clone, Default)]
+pub struct VentField {
+    segments: Vec<LineSegment>,
+    lines: HashMap<Line, Vec<Piece>>,
+    // Indexes of the segments lying on each line.
+    members: HashMap<Line, Vec<usize>>,
+    // Whether any segment was written in 3D, in which case points are too.
+    three_d: bool,
+}
+
+impl VentField {
+    pub fn new(segments: &[LineSegment], filter: Filter) -> VentField {
+        let mut events: HashMap<Line, Vec<(i128, i64)>> = HashMap::new();
+        let mut members: HashMap<Line, Vec<usize>> = HashMap::new();
+        for (idx, seg) in segments.iter().enumerate().filter(|(_, x)| filter.allows(x)) {
+            // Single points don't have a direction, so they're filed along the x axis.
+            let step = if seg.c1 == seg.c2 { [1, 0, 0] } else { seg.step() };
+            let line = Line::through(seg.c1, step);
+            let (p1, p2) = (line.position(seg.c1), line.position(seg.c2));
+            let line_events = events.entry(line).or_default();
+            line_events.push((p1.min(p2), 1));
+            line_events.push((p1.max(p2) + 1, -1));
+            members.entry(line).or_default().push(idx);
+        }
+
+        let mut lines = HashMap::new();
+        for (line, mut line_events) in events {
+            line_events.sort_unstable();
+            let mut pieces = vec![];
+            let mut count = 0i64;
+            for window in line_events.windows(2) {
+                count += window[0].1;
+                if count > 0 && window[0].0 != window[1].0 {
+                    pieces.push(Piece { start: window[0].0, end: window[1].0, count: count as u64 });
+                }
+            }
+            lines.insert(line, pieces);
+        }
+        VentField { segments: segments.to_vec(), lines, members, three_d: segments.iter().any(|x| x.three_d) }
+    }
+
+    pub fn segments(&self) -> &[LineSegment] {
+        &self.segments
+    }
+
+    pub fn three_d(&self) -> bool {
+        self.three_d
+    }
+
+    fn line_coverage(&self, line: &Line, c: Coord) -> u64 {
+        let pieces = match self.lines.get(line) {
+            Some(pieces) => pieces,
+            None => return 0,
+        };
+        let pos = line.position(c);
+        match pieces.partition_point(|x| x.start <= pos) {
+            0 => 0,
+            idx if pos < pieces[idx - 1].end => pieces[idx - 1].count,
+            _ => 0,
+        }
+    }
+
+    fn directions(&self) -> HashSet<[i128; 3]> {
+        self.lines.keys().map(|x| x.dir).collect()
+    }
+
+    // Number of segments covering a point.
+    pub fn coverage(&self, c: Coord) -> u64 {
+        self.directions().into_iter()
+            .map(|dir| self.line_coverage(&Line::through(c, dir), c))
+            .sum()
+    }
+
+    // Points covered by segments running in more than one direction, and the lines through them.
+    fn crossings(&self) -> HashMap<Coord, HashSet<Line>> {
+        let lines = self.lines.keys().collect::<Vec<&Line>>();
+        let mut crossings: HashMap<Coord, HashSet<Line>> = HashMap::new();
+        for (idx, a) in lines.iter().enumerate() {
+            for b in lines[idx + 1..].iter() {
+                if let Some(c) = a.intersection(b) {
+                    if self.line_coverage(a, c) > 0 && self.line_coverage(b, c) > 0 {
+                        let through = crossings.entry(c).or_default();
+                        through.insert(**a);
+                        through.insert(**b);
+                    }
+                }
+            }
+        }
+        crossings
+    }
+
+    // Number of points covered by at least k segments.
+    pub fn count_at_least(&self, k: u64) -> u128 {
+        let k = k.max(1);
+        // Start with each line on its own, which is only wrong at crossings: there the point may have
+        // been counted by several lines, or by none even though their combined coverage reaches k.
+        let mut total = 0i128;
+        for pieces in self.lines.values() {
+            for piece in pieces.iter().filter(|x| x.count >= k) {
+                total += piece.end - piece.start;
+            }
+        }
+
+        for (c, through) in self.crossings() {
+            let counts = through.iter().map(|line| self.line_coverage(line, c)).collect::<Vec<u64>>();
+            total -= counts.iter().filter(|x| **x >= k).count() as i128;
+            if counts.iter().sum::<u64>() >= k {
+                total += 1;
+            }
+        }
+        total as u128
+    }
+
+    pub fn overlaps(&self) -> u128 {
+        self.count_at_least(2)
+    }
+
+    // Indexes of the segments covering a point.
+    pub fn covering(&self, c: Coord) -> Vec<usize> {
+        let mut covering = vec![];
+        for dir in self.directions() {
+            let line = Line::through(c, dir);
+            let pos = line.position(c);
+            for idx in self.members.get(&line).into_iter().flatten().copied() {
+                let seg = &self.segments[idx];
+                let (p1, p2) = (line.position(seg.c1), line.position(seg.c2));
+                if p1.min(p2) <= pos && pos <= p1.max(p2) {
+                    covering.push(idx);
+                }
+            }
+        }
+        covering.sort_unstable();
+        covering
+    }
+
+    // Every point covered by at least k segments along with its coverage, in coordinate order. Long
+    // overlapping segments make for a lot of points, so check count_at_least first.
+    pub fn points_at_least(&self, k: u64) -> Vec<(Coord, u64)> {
+        let k = k.max(1);
+        let crossings = self.crossings();
+
+        let mut points = vec![];
+        for (line, pieces) in self.lines.iter() {
+            for piece in pieces.iter().filter(|x| x.count >= k) {
+                let mut pos = piece.start;
+                while pos < piece.end {
+                    let c = line.at(pos);
+                    if !crossings.contains_key(&c) {
+                        points.push((c, piece.count));
+                    }
+                    pos += 1;
+                }
+            }
+        }
+        for (c, through) in crossings {
+            let count = through.iter().map(|line| self.line_coverage(line, c)).sum::<u64>();
+            if count >= k {
+                points.push((c, count));
+            }
+        }
+        points.sort_unstable();
+        points
+    }
+
+    pub fn overlap_points(&self) -> Vec<(Coord, u64)> {
+        self.points_at_least(2)
+    }
+
+    // The point covered by the most segments, picking the lowest coordinate if there's a tie.
+    pub fn densest(&self) -> Option<(Coord, u64)> {
+        // A piece's first point is its lowest coordinate, since lines run up their first moving axis.
+        let pieces = self.lines.iter()
+            .flat_map(|(line, pieces)| pieces.iter().map(move |x| (line.at(x.start), x.count)));
+        let crossings = self.crossings().into_iter()
+            .map(|(c, through)| (c, through.iter().map(|line| self.line_coverage(line, c)).sum::<u64>()));
+        pieces.chain(crossings).min_by_key(|(c, count)| (std::cmp::Reverse(*count), *c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_bigint::BigInt;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::{intersect, parse_segments, Coord, Filter, LineSegment, VentField};
+
+    const EXAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2
+";
+
+    #[test]
+    fn example() {
+        let segments = parse_segments(EXAMPLE).unwrap();
+        assert_eq!(VentField::new(&segments, Filter::AxisAligned).overlaps(), 5);
+        let field = VentField::new(&segments, Filter::Diagonal);
+        assert_eq!(field.overlaps(), 12);
+        assert_eq!(field.coverage(Coord::new(7, 4)), 2);
+        assert_eq!(field.coverage(Coord::new(9, 9)), 0);
+    }
+
+    #[test]
+    fn huge_coordinates() {
+        let segments = parse_segments("-5000000000,0 -> 5000000000,0\n0,-3 -> 0,3\n-3,-3 -> 3,3\n100,0 -> 200,0\n").unwrap();
+        let field = VentField::new(&segments, Filter::Diagonal);
+        assert_eq!(field.overlaps(), 102);
+        assert_eq!(field.count_at_least(3), 1);
+        assert_eq!(field.count_at_least(1), 10_000_000_001 + 6 + 6);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0x9e3779b97f4a7c15);
+        let mut rand = |n: i64| rng.gen_range(0..n);
+        for round in 0..400 {
+            // Every other round is in 3D, squashed into a small cube so that lines actually meet.
+            let depth = if round % 2 == 0 { 1 } else { 4 };
+            let segments = (0..1 + rand(12)).map(|_| {
+                let c1 = Coord { x: rand(16) - 8, y: rand(16) - 8, z: rand(depth) };
+                let len = rand(6);
+                let (dx, dy, dz) = (rand(7) - 3, rand(7) - 3, if depth > 1 { rand(3) - 1 } else { 0 });
+                LineSegment { c1, c2: Coord { x: c1.x + dx * len, y: c1.y + dy * len, z: c1.z + dz * len }, three_d: depth > 1 }
+            }).collect::<Vec<LineSegment>>();
+
+            let mut grid: HashMap<Coord, u64> = HashMap::new();
+            for seg in segments.iter() {
+                for c in seg.points() {
+                    *grid.entry(c).or_default() += 1;
+                }
+            }
+
+            let field = VentField::new(&segments, Filter::All);
+            for a in field.lines.keys() {
+                for b in field.lines.keys().filter(|x| *x != a) {
+                    assert_eq!(intersect::<i128>(a, b), intersect::<BigInt>(a, b));
+                }
+            }
+            for k in 1..=4 {
+                assert_eq!(field.count_at_least(k), grid.values().filter(|x| **x >= k).count() as u128);
+            }
+            for (c, count) in grid.iter() {
+                assert_eq!(field.coverage(*c), *count);
+                assert_eq!(field.covering(*c).len() as u64, *count);
+            }
+            let mut expected = grid.iter().filter(|(_, x)| **x >= 2).map(|(c, x)| (*c, *x)).collect::<Vec<(Coord, u64)>>();
+            expected.sort_unstable();
+            assert_eq!(field.overlap_points(), expected);
+            let max = grid.values().max().copied();
+            assert_eq!(field.densest().map(|x| x.1), max);
+            if let Some((c, count)) = field.densest() {
+                assert_eq!(grid[&c], count);
+            }
+        }
+    }
+
+    #[test]
+    fn queries() {
+        let segments = parse_segments(EXAMPLE).unwrap();
+        let field = VentField::new(&segments, Filter::Diagonal);
+        assert_eq!(field.covering(Coord::new(7, 4)), vec![2, 4]);
+        assert_eq!(field.covering(Coord::new(4, 4)), vec![1, 2, 8]);
+        assert_eq!(field.covering(Coord::new(9, 9)), vec![]);
+        assert_eq!(field.densest(), Some((Coord::new(4, 4), 3)));
+
+        let points = field.overlap_points();
+        assert_eq!(points.len(), 12);
+        assert_eq!(points[0], (Coord::new(0, 9), 2));
+        assert!(points.contains(&(Coord::new(4, 4), 3)));
+
+        let axis_aligned = VentField::new(&segments, Filter::AxisAligned);
+        assert_eq!(axis_aligned.covering(Coord::new(4, 4)), vec![2]);
+        assert_eq!(axis_aligned.densest(), Some((Coord::new(0, 9), 2)));
+        assert_eq!(VentField::new(&[], Filter::All).densest(), None);
+    }
+
+    #[test]
+    fn three_dimensions() {
+        let segments = parse_segments("0,0,0 -> 4,4,4\n4,0,0 -> 0,4,4\n2,2,-9 -> 2,2,9\n0,0 -> 0,4\n0,0,1 -> 3,3,1\n").unwrap();
+        assert!(segments[0].is_diagonal() && segments[2].is_axis_aligned() && !segments[2].is_diagonal());
+        assert_eq!(segments[3].c2, Coord::new(0, 4));
+
+        let field = VentField::new(&segments, Filter::Diagonal);
+        assert_eq!(field.overlap_points(), vec![
+            (Coord::new(0, 0), 2),
+            (Coord { x: 1, y: 1, z: 1 }, 2),
+            (Coord { x: 2, y: 2, z: 1 }, 2),
+            (Coord { x: 2, y: 2, z: 2 }, 3),
+        ]);
+        assert_eq!(field.covering(Coord { x: 2, y: 2, z: 2 }), vec![0, 1, 2]);
+        assert_eq!(field.covering(Coord::new(0, 0)), vec![0, 3]);
+        assert_eq!(field.count_at_least(1), 5 + 5 + 19 + 5 + 4 - 5);
+        assert_eq!(VentField::new(&segments, Filter::AxisAligned).overlaps(), 0);
+        assert_eq!(format!("{}", segments[0]), "0,0,0 -> 4,4,4");
+        assert_eq!(format!("{}", segments[3]), "0,0 -> 0,4");
+
+        // Vents in space at z = 0 are still printed in 3D.
+        let field = VentField::new(&parse_segments("1,1,0 -> 3,3,0\n1,1,0 -> 1,3,0\n").unwrap(), Filter::All);
+        let points = field.overlap_points().iter().map(|(c, count)| format!("{} {}", c.format(field.three_d()), count)).collect::<Vec<String>>();
+        assert_eq!(points, vec!["1,1,0 2"]);
+        assert_eq!(field.densest().map(|(c, _)| c.format(field.three_d())).as_deref(), Some("1,1,0"));
+        let flat = VentField::new(&parse_segments("1,1 -> 3,3\n1,1 -> 1,3\n").unwrap(), Filter::All);
+        assert_eq!(flat.densest().map(|(c, _)| c.format(flat.three_d())).as_deref(), Some("1,1"));
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!("1,2 -> 3".parse::<LineSegment>().is_err());
+        assert!("1,2,3,4 -> 1,2".parse::<LineSegment>().is_err());
+        assert!("1,2 -> 3,x".parse::<LineSegment>().is_err());
+        assert!("1,2 -> 3,4 -> 5,6".parse::<LineSegment>().is_err());
+        assert!("0,0 -> 1,1,1".parse::<LineSegment>().is_err());
+        assert!("1,2 -> 3,4".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn arbitrary_slopes() {
+        let seg = "2,0 -> 8,3".parse::<LineSegment>().unwrap();
+        assert_eq!(seg.step(), [2, 1, 0]);
+        assert_eq!(seg.points().collect::<Vec<Coord>>(), vec![
+            Coord::new(2, 0), Coord::new(4, 1), Coord::new(6, 2), Coord::new(8, 3),
+        ]);
+
+        let segments = parse_segments("2,0 -> 8,3\n4,0 -> 4,5\n0,0 -> 5,5\n").unwrap();
+        assert_eq!(VentField::new(&segments, Filter::AxisAligned).count_at_least(1), 6);
+        assert_eq!(VentField::new(&segments, Filter::Diagonal).overlaps(), 1);
+        assert_eq!(VentField::new(&segments, Filter::All).overlaps(), 2);
+
+        // Steps too long for i64, and lines steep enough that crossing them needs big integers.
+        let segments = parse_segments("0,0 -> 3000000000,1\n0,0 -> 5,0\n").unwrap();
+        assert_eq!(segments[0].step(), [3000000000, 1, 0]);
+        assert_eq!(VentField::new(&segments, Filter::All).count_at_least(1), 2 + 6 - 1);
+        let segments = parse_segments("\
+            -9223372036854775808,0 -> 9223372036854775807,1
+            -9223372036854775808,1 -> 9223372036854775807,0
+            9223372036854775807,-1 -> 9223372036854775807,1
+            0,0 -> 6000000000,2
+            3000000000,0 -> 3000000000,5
+        ").unwrap();
+        assert_eq!(segments[0].step(), [u64::MAX as i128, 1, 0]);
+        let field = VentField::new(&segments, Filter::All);
+        assert_eq!(field.overlap_points(), vec![
+            (Coord::new(3000000000, 1), 2),
+            (Coord::new(i64::MAX, 0), 2),
+            (Coord::new(i64::MAX, 1), 2),
+        ]);
+        assert_eq!(field.count_at_least(1), 2 + 2 + 3 + 3 + 6 - 3);
+        assert_eq!(field.covering(Coord::new(i64::MAX, 1)), vec![0, 2]);
+    }
+}
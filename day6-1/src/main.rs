@@ -0,0 +1,5 @@
+const DAYS: u64 = 80;
+
+fn main() -> Result<(), String> {
+    day6::cli::main(DAYS)
+}
@@ -0,0 +1,55 @@
+use std::io::BufRead;
+
+use num_bigint::BigUint;
+
+use crate::stochastic::{summarise, Stochastic};
+use crate::Model;
+
+// Both day6 binaries, counting lanternfish for `default_days` unless --days says otherwise.
+// --series=csv or --series=json prints the population for every day instead of just the last, and
+// --replicates=N runs that many stochastic simulations (see --spawn-probability, --mortality and
+// --seed) and prints their spread per day as CSV.
+pub fn main(default_days: u64) -> Result<(), String> {
+    let stdin = std::io::stdin();
+
+    let default = Model::default();
+    let model = Model::new(
+        args::value("period")?.unwrap_or(default.period()),
+        args::value("newborn-delay")?.unwrap_or(default.newborn_delay()),
+        args::value("max-age")?,
+    )?;
+    let days = args::value("days")?.unwrap_or(default_days);
+
+    let line = stdin.lock().lines().next().ok_or("no lanternfish")?.map_err(|e| e.to_string())?;
+    let lanternfishy_cohorts = model.parse_cohorts(&line)?;
+
+    if let Some(replicates) = args::value::<usize>("replicates")? {
+        let stochastic = Stochastic::new(
+            args::value("spawn-probability")?.unwrap_or(1.0),
+            args::value("mortality")?.unwrap_or(0.0),
+        )?;
+        let runs = stochastic.replicates(&model, &lanternfishy_cohorts, days, replicates, args::value("seed")?.unwrap_or(0))?;
+        println!("day,deterministic,mean,p5,p25,p50,p75,p95");
+        for day in summarise(&model, &lanternfishy_cohorts, &runs, &[5.0, 25.0, 50.0, 75.0, 95.0])? {
+            println!("{},{},{:.2},{}", day.day, day.deterministic, day.mean,
+                day.percentiles.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(","));
+        }
+        return Ok(());
+    }
+
+    match args::value::<String>("series")?.as_deref() {
+        Some("csv") => print!("{}", model.series(&lanternfishy_cohorts, days)?.to_csv()),
+        Some("json") => print!("{}", model.series(&lanternfishy_cohorts, days)?.to_json()),
+        Some(format) => return Err(format!("--series: unknown format '{}', expected csv or json", format)),
+        None => {
+            // When the lanternfishies outnumber the atoms in the galaxy, we must count them as cohorts.
+            let cohorts = model.cohorts_after(&lanternfishy_cohorts, days)?;
+
+            println!("After {:02} days: {} ({} total)",
+                days,
+                cohorts.iter().enumerate().map(|(age, count)| format!("{}: {}", age, count)).collect::<Vec<String>>().join(" "),
+                cohorts.iter().sum::<BigUint>());
+        }
+    }
+    Ok(())
+}
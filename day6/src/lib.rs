@@ -0,0 +1,527 @@
+use std::collections::VecDeque;
+
+use num_bigint::BigUint;
+
+pub mod cli;
+pub mod stochastic;
+
+// How a species of lanternfish lives. Every fish spawns a newborn each `period` days, newborns take an
+// extra `newborn_delay` days before their first cycle, and if there's a `max_age` fish die on
+// reaching it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Model {
+    period: usize,
+    newborn_delay: usize,
+    max_age: Option<usize>,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model { period: 7, newborn_delay: 2, max_age: None }
+    }
+}
+
+impl Model {
+    pub fn new(period: usize, newborn_delay: usize, max_age: Option<usize>) -> Result<Model, String> {
+        if period == 0 {
+            return Err("reproduction period must be at least 1 day".to_string());
+        }
+        let model = Model { period, newborn_delay, max_age };
+        if let Some(max_age) = max_age {
+            // Input fish are taken to be as young as their timer allows, so the oldest of them is
+            // newborn_timer days old, and has to still be alive.
+            if max_age <= model.newborn_timer() {
+                return Err(format!("maximum age must be over {} so fish live to spawn, got {}", model.newborn_timer(), max_age));
+            }
+        }
+        Ok(model)
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    pub fn newborn_delay(&self) -> usize {
+        self.newborn_delay
+    }
+
+    pub fn max_age(&self) -> Option<usize> {
+        self.max_age
+    }
+
+    // Timer value fish go back to after spawning.
+    pub fn reset_timer(&self) -> usize {
+        self.period - 1
+    }
+
+    // Timer value newborns start at, which is also the highest a timer goes.
+    pub fn newborn_timer(&self) -> usize {
+        self.period - 1 + self.newborn_delay
+    }
+
+    pub fn timers(&self) -> usize {
+        self.newborn_timer() + 1
+    }
+
+    // Timer of a fish that's `age` days old.
+    pub fn timer_at_age(&self, age: usize) -> usize {
+        let newborn = self.newborn_timer();
+        if age <= newborn {
+            newborn - age
+        } else {
+            self.reset_timer() - (age - newborn - 1) % self.period
+        }
+    }
+
+    pub fn parse_cohorts(&self, s: &str) -> Result<Vec<u64>, String> {
+        let mut cohorts = vec![0u64; self.timers()];
+        for timer in s.trim().split(',') {
+            let timer = timer.trim().parse::<usize>().map_err(|e| format!("lanternfish timer '{}': {}", timer, e))?;
+            if timer >= cohorts.len() {
+                return Err(format!("lanternfish timer {} is over {}", timer, cohorts.len() - 1));
+            }
+            cohorts[timer] += 1;
+        }
+        Ok(cohorts)
+    }
+
+    // Without deaths, all that matters about a fish is its timer. Once fish die of old age their age
+    // matters too, and since age decides the timer, the states are just ages.
+    fn states(&self) -> usize {
+        self.max_age.unwrap_or(self.timers())
+    }
+
+    // Where the fish in each state are the next day (None if they die of old age), and whether they
+    // spawn a newborn on the way.
+    fn successors(&self) -> Vec<(Option<usize>, bool)> {
+        match self.max_age {
+            // Everyone's timer drops by one, and the fish at 0 reset and spawn a newborn.
+            None => (0..self.timers())
+                .map(|timer| if timer == 0 { (Some(self.reset_timer()), true) } else { (Some(timer - 1), false) })
+                .collect(),
+            // Everyone gets a day older unless that's the end of them, and the fish whose timer has
+            // hit 0 spawn a newborn on the way.
+            Some(max_age) => (0..max_age)
+                .map(|age| (if age + 1 < max_age { Some(age + 1) } else { None }, self.timer_at_age(age) == 0))
+                .collect(),
+        }
+    }
+
+    fn newborn_state(&self) -> usize {
+        match self.max_age {
+            None => self.newborn_timer(),
+            Some(_) => 0,
+        }
+    }
+
+    // One day's worth of cohort shifting: new[i] = sum of transition[i][j] * old[j]. There's none
+    // with deaths, as it would need a row and a column for every age.
+    pub fn transition(&self) -> Option<Matrix> {
+        if self.max_age.is_some() {
+            return None;
+        }
+        let mut m = Matrix::zero(self.states());
+        for (state, (next, spawns)) in self.successors().into_iter().enumerate() {
+            if let Some(next) = next {
+                m.set(next, state, m.get(next, state) + 1u32);
+            }
+            if spawns {
+                m.set(self.newborn_state(), state, m.get(self.newborn_state(), state) + 1u32);
+            }
+        }
+        Some(m)
+    }
+
+    fn initial_state(&self, cohorts: &[u64]) -> Result<Vec<BigUint>, String> {
+        if cohorts.len() != self.timers() {
+            return Err(format!("expected {} cohorts, got {}", self.timers(), cohorts.len()));
+        }
+        let mut state = vec![BigUint::default(); self.states()];
+        for (timer, count) in cohorts.iter().enumerate() {
+            let state_idx = match self.max_age {
+                None => timer,
+                Some(_) => self.newborn_timer() - timer,
+            };
+            state[state_idx] += *count;
+        }
+        Ok(state)
+    }
+
+    fn timer_breakdown(&self, state: Vec<BigUint>) -> Vec<BigUint> {
+        match self.max_age {
+            None => state,
+            Some(_) => {
+                let mut cohorts = vec![BigUint::default(); self.timers()];
+                for (age, count) in state.into_iter().enumerate() {
+                    cohorts[self.timer_at_age(age)] += count;
+                }
+                cohorts
+            }
+        }
+    }
+
+    // One day on from `state`, given the model's successors.
+    fn step(&self, successors: &[(Option<usize>, bool)], state: &[BigUint]) -> Vec<BigUint> {
+        let mut next = vec![BigUint::default(); state.len()];
+        for ((to, spawns), count) in successors.iter().zip(state) {
+            if let Some(to) = to {
+                next[*to] += count;
+            }
+            if *spawns {
+                next[self.newborn_state()] += count;
+            }
+        }
+        next
+    }
+
+    // How many fish there are at each timer value after the given number of days, in O(log days)
+    // matrix products. With deaths it's whichever of ages_after's two ways looks cheaper.
+    pub fn cohorts_after(&self, initial: &[u64], days: u64) -> Result<Vec<BigUint>, String> {
+        let state = self.initial_state(initial)?;
+        if let Some(transition) = self.transition() {
+            return Ok(self.timer_breakdown(transition.pow(days).apply(&state)));
+        }
+        let max_age = state.len();
+        let stepping = days as u128 * 3;
+        let power = (max_age * max_age) as u128 * (64 - days.leading_zeros()) as u128;
+        Ok(self.timer_breakdown(self.ages_after(state, days, stepping > power)))
+    }
+
+    // With deaths, the fish aged a on day t are the ones born on day t - a, so a state is the last
+    // max_age days of births. Numbering births so that x[i] for i < max_age is the initial fish aged
+    // max_age - 1 - i, x[i] is the sum of x[i - 1 - a] over the ages a fish spawn at: a linear
+    // recurrence of order max_age. Those ages are a period apart, so once there's a period's worth of
+    // births x[i] - x[i - period] is just the youngest spawners' births less those of the fish that
+    // died, and stepping a day at a time takes a couple of additions. Or by_power jumps straight to
+    // day t with X^t modulo the recurrence's characteristic polynomial, which is max_age² products
+    // per bit of t but never needs more than 2 * max_age numbers at once.
+    fn ages_after(&self, ages: Vec<BigUint>, days: u64, by_power: bool) -> Vec<BigUint> {
+        let m = ages.len();
+        let spawning = (0..m).filter(|x| self.timer_at_age(*x) == 0).collect::<Vec<usize>>();
+        if !by_power {
+            let (youngest, oldest) = (spawning[0] + 1, spawning[spawning.len() - 1] + 1 + self.period);
+            let mut births = ages.into_iter().rev().collect::<VecDeque<BigUint>>();
+            for day in 0..days {
+                let back = |lag: usize| &births[births.len() - lag];
+                let born = match day < self.period as u64 {
+                    true => spawning.iter().map(|a| back(a + 1)).sum::<BigUint>(),
+                    false => back(self.period) + back(youngest) - back(oldest),
+                };
+                births.push_back(born);
+                if births.len() > m + self.period {
+                    births.pop_front();
+                }
+            }
+            return births.into_iter().rev().take(m).collect();
+        }
+
+        // x[t + j] = sum of r[i] * x[i + j], for r = X^t mod the polynomial.
+        let mut births = ages.into_iter().rev().collect::<Vec<BigUint>>();
+        for i in m..2 * m - 1 {
+            let born = spawning.iter().map(|a| &births[i - 1 - a]).sum::<BigUint>();
+            births.push(born);
+        }
+        let r = recurrence_power(&spawning, m, days);
+        (0..m).rev()
+            .map(|j| r.iter().enumerate().filter(|(_, x)| x.bits() != 0).map(|(i, x)| x * &births[i + j]).sum())
+            .collect()
+    }
+
+    pub fn population_after(&self, initial: &[u64], days: u64) -> Result<BigUint, String> {
+        Ok(self.cohorts_after(initial, days)?.into_iter().sum())
+    }
+
+    // Cohorts for every day from 0 up to and including `days`, stepping a day at a time.
+    pub fn series(&self, initial: &[u64], days: u64) -> Result<Series, String> {
+        let successors = self.successors();
+        let mut state = self.initial_state(initial)?;
+        let mut cohorts = Vec::with_capacity(days as usize + 1);
+        for day in 0..=days {
+            if day > 0 {
+                state = self.step(&successors, &state);
+            }
+            cohorts.push(self.timer_breakdown(state.clone()));
+        }
+        Ok(Series { cohorts })
+    }
+}
+
+// X^exp modulo the characteristic polynomial of x[i] = sum of x[i - 1 - a] over `spawning`, which
+// has degree m, so X^m = sum of X^(m - 1 - a). Polynomials are coefficients, lowest degree first.
+fn recurrence_power(spawning: &[usize], m: usize, exp: u64) -> Vec<BigUint> {
+    let reduce = |mut poly: Vec<BigUint>| {
+        for d in (m..poly.len()).rev() {
+            let q = std::mem::take(&mut poly[d]);
+            if q.bits() != 0 {
+                for a in spawning {
+                    poly[d - 1 - a] += &q;
+                }
+            }
+        }
+        poly.resize(m, BigUint::default());
+        poly
+    };
+    // Each cross term turns up twice, so it's worked out once and doubled.
+    let square = |poly: &[BigUint]| {
+        let mut product = vec![BigUint::default(); 2 * poly.len() - 1];
+        for (i, x) in poly.iter().enumerate().filter(|(_, x)| x.bits() != 0) {
+            product[2 * i] += x * x;
+            for (j, y) in poly.iter().enumerate().skip(i + 1).filter(|(_, y)| y.bits() != 0) {
+                product[i + j] += (x * y) << 1;
+            }
+        }
+        reduce(product)
+    };
+
+    // Left to right over the bits of exp, so multiplying by X is only ever a shift.
+    let mut result = reduce(vec![BigUint::from(1u32)]);
+    for bit in (0..64 - exp.leading_zeros()).rev() {
+        result = square(&result);
+        if exp >> bit & 1 == 1 {
+            result.insert(0, BigUint::default());
+            result = reduce(result);
+        }
+    }
+    result
+}
+
+// Population per day, broken down by timer, for plotting growth curves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Series {
+    // Indexed by day, then by timer.
+    pub cohorts: Vec<Vec<BigUint>>,
+}
+
+impl Series {
+    pub fn totals(&self) -> Vec<BigUint> {
+        self.cohorts.iter().map(|x| x.iter().sum()).collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let timers = self.cohorts.first().map(|x| x.len()).unwrap_or(0);
+        let mut csv = String::from("day,total");
+        for timer in 0..timers {
+            csv += &format!(",timer_{}", timer);
+        }
+        csv.push('\n');
+        for (day, (cohorts, total)) in self.cohorts.iter().zip(self.totals()).enumerate() {
+            csv += &format!("{},{}", day, total);
+            for count in cohorts {
+                csv += &format!(",{}", count);
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    // Counts are written as plain JSON numbers however big they get, so readers that parse numbers
+    // into doubles will lose precision on the later days.
+    pub fn to_json(&self) -> String {
+        let days = self.cohorts.iter().zip(self.totals()).enumerate()
+            .map(|(day, (cohorts, total))| format!(
+                "{{\"day\":{},\"total\":{},\"cohorts\":[{}]}}",
+                day,
+                total,
+                cohorts.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(","),
+            ))
+            .collect::<Vec<String>>();
+        format!("[{}]\n", days.join(",\n"))
+    }
+}
+
+// Square matrix of big integers, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    n: usize,
+    cells: Vec<BigUint>,
+}
+
+impl Matrix {
+    pub fn zero(n: usize) -> Matrix {
+        Matrix { n, cells: vec![BigUint::default(); n * n] }
+    }
+
+    pub fn identity(n: usize) -> Matrix {
+        let mut m = Matrix::zero(n);
+        for i in 0..n {
+            m.cells[i * n + i] = BigUint::from(1u32);
+        }
+        m
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &BigUint {
+        &self.cells[row * self.n + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: BigUint) {
+        self.cells[row * self.n + col] = value;
+    }
+
+    pub fn mul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.n, other.n);
+        let n = self.n;
+        let mut result = Matrix::zero(n);
+        for i in 0..n {
+            for k in 0..n {
+                let lhs = self.get(i, k);
+                if lhs.bits() == 0 {
+                    continue;
+                }
+                for j in 0..n {
+                    let rhs = other.get(k, j);
+                    if rhs.bits() != 0 {
+                        result.cells[i * n + j] += lhs * rhs;
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    pub fn pow(&self, mut exp: u64) -> Matrix {
+        let mut result = Matrix::identity(self.n);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+
+    pub fn apply(&self, v: &[BigUint]) -> Vec<BigUint> {
+        assert_eq!(self.n, v.len());
+        (0..self.n)
+            .map(|i| (0..self.n).map(|j| self.get(i, j) * &v[j]).sum())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::Model;
+
+    #[test]
+    fn example() {
+        let model = Model::default();
+        let initial = model.parse_cohorts("3,4,3,1,2").unwrap();
+        assert_eq!(model.population_after(&initial, 18).unwrap(), BigUint::from(26u32));
+        assert_eq!(model.population_after(&initial, 80).unwrap(), BigUint::from(5934u32));
+        assert_eq!(model.population_after(&initial, 256).unwrap(), BigUint::from(26984457539u64));
+    }
+
+    #[test]
+    fn matches_day_by_day() {
+        let model = Model::default();
+        let initial = model.parse_cohorts("0,1,1,2,5,6,8,8,8").unwrap();
+        let mut cohorts = initial.clone();
+        for day in 0..=300u64 {
+            let expected = cohorts.iter().map(|x| BigUint::from(*x)).collect::<Vec<BigUint>>();
+            assert_eq!(model.cohorts_after(&initial, day).unwrap(), expected);
+
+            cohorts.rotate_left(1);
+            cohorts[6] += cohorts[8];
+        }
+    }
+
+    // Follows every fish individually, as (timer, age) pairs.
+    fn simulate_fish(model: &Model, timers: &[usize], days: u64) -> Vec<u64> {
+        let mut fish = timers.iter().map(|t| (*t, model.newborn_timer() - *t)).collect::<Vec<(usize, usize)>>();
+        for _ in 0..days {
+            let mut next = vec![];
+            for (timer, age) in fish {
+                if timer == 0 {
+                    next.push((model.newborn_timer(), 0));
+                }
+                if model.max_age().map(|max| age + 1 < max).unwrap_or(true) {
+                    next.push((if timer == 0 { model.reset_timer() } else { timer - 1 }, age + 1));
+                }
+            }
+            fish = next;
+        }
+        let mut cohorts = vec![0; model.timers()];
+        for (timer, _) in fish {
+            cohorts[timer] += 1;
+        }
+        cohorts
+    }
+
+    #[test]
+    fn variant_species() {
+        for (period, delay, max_age) in [(7, 2, None), (3, 0, None), (1, 1, None), (7, 2, Some(9)), (7, 2, Some(20)), (4, 3, Some(13)), (1, 0, Some(3))] {
+            let model = Model::new(period, delay, max_age).unwrap();
+            let timers = (0..model.timers()).chain([0, 0, model.newborn_timer()]).collect::<Vec<usize>>();
+            let initial = model.parse_cohorts(&timers.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",")).unwrap();
+            for days in 0..16 {
+                let expected = simulate_fish(&model, &timers, days).into_iter().map(BigUint::from).collect::<Vec<BigUint>>();
+                assert_eq!(model.cohorts_after(&initial, days).unwrap(), expected, "{:?} after {} days", model, days);
+            }
+        }
+    }
+
+    #[test]
+    fn long_lived() {
+        let model = Model::new(4, 3, Some(13)).unwrap();
+        let initial = model.parse_cohorts("0,1,2,3,4,5,6,0,0,6").unwrap();
+        for days in [0, 1, 12, 13, 100, 257] {
+            let state = model.initial_state(&initial).unwrap();
+            let stepped = model.ages_after(state.clone(), days, false);
+            assert_eq!(model.ages_after(state, days, true), stepped, "after {} days", days);
+            assert_eq!(model.cohorts_after(&initial, days).unwrap(), model.timer_breakdown(stepped));
+        }
+        assert_eq!(model.series(&initial, 100).unwrap().cohorts[100], model.cohorts_after(&initial, 100).unwrap());
+
+        // Far too many ages for a transition matrix.
+        let model = Model::new(7, 2, Some(50_000)).unwrap();
+        assert!(model.transition().is_none());
+        let initial = model.parse_cohorts("3").unwrap();
+        assert_eq!(model.population_after(&initial, 10).unwrap(), BigUint::from(2u32));
+        let model = Model::new(7, 2, Some(100)).unwrap();
+        assert!(model.population_after(&initial, 100_000).unwrap().bits() > 10_000);
+    }
+
+    #[test]
+    fn series() {
+        let model = Model::default();
+        let initial = model.parse_cohorts("3,4,3,1,2").unwrap();
+        let series = model.series(&initial, 80).unwrap();
+        assert_eq!(series.cohorts.len(), 81);
+        assert_eq!(series.totals()[18], BigUint::from(26u32));
+        assert_eq!(series.totals()[80], BigUint::from(5934u32));
+        assert_eq!(series.cohorts[80], model.cohorts_after(&initial, 80).unwrap());
+
+        let series = model.series(&initial, 1).unwrap();
+        assert_eq!(series.to_csv(), "day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8\n\
+            0,5,0,1,1,2,1,0,0,0,0\n\
+            1,5,1,1,2,1,0,0,0,0,0\n");
+        assert_eq!(series.to_json(), "[{\"day\":0,\"total\":5,\"cohorts\":[0,1,1,2,1,0,0,0,0]},\n\
+            {\"day\":1,\"total\":5,\"cohorts\":[1,1,2,1,0,0,0,0,0]}]\n");
+    }
+
+    #[test]
+    fn huge_populations() {
+        let model = Model::default();
+        let initial = model.parse_cohorts("3").unwrap();
+        let total = model.population_after(&initial, 10_000).unwrap();
+        assert!(total.bits() > 1000);
+        let cohorts = model.cohorts_after(&initial, 10_000).unwrap();
+        assert_eq!(cohorts.iter().sum::<BigUint>(), total);
+    }
+
+    #[test]
+    fn bad_input() {
+        let model = Model::default();
+        assert!(model.parse_cohorts("1,9").is_err());
+        assert!(model.parse_cohorts("1,,2").is_err());
+        assert!(model.cohorts_after(&[1, 2, 3], 10).is_err());
+        assert!(Model::new(0, 2, None).is_err());
+        assert!(Model::new(7, 2, Some(8)).is_err());
+        assert!(Model::new(7, 2, Some(9)).is_ok());
+    }
+}
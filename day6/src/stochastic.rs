@@ -0,0 +1,181 @@
+use num_bigint::BigUint;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Binomial, Distribution};
+
+use crate::Model;
+
+// Fish that don't always do what the model says: when a fish's timer runs out it only spawns with
+// `spawn_probability`, and every day each fish dies with probability `mortality`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stochastic {
+    spawn_probability: f64,
+    mortality: f64,
+}
+
+impl Stochastic {
+    pub fn new(spawn_probability: f64, mortality: f64) -> Result<Stochastic, String> {
+        for (name, p) in [("spawn probability", spawn_probability), ("mortality", mortality)] {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, p));
+            }
+        }
+        Ok(Stochastic { spawn_probability, mortality })
+    }
+
+    pub fn spawn_probability(&self) -> f64 {
+        self.spawn_probability
+    }
+
+    pub fn mortality(&self) -> f64 {
+        self.mortality
+    }
+
+    // Cohorts are still tracked as counts, with the births and deaths in each drawn from a binomial,
+    // so it's no slower than the deterministic model no matter how many fish there are. Returns the
+    // total population for each day from 0 up to and including `days`.
+    pub fn simulate<R>(&self, model: &Model, initial: &[u64], days: u64, rng: &mut R) -> Result<Vec<u64>, String> where R: Rng {
+        let successors = model.successors();
+        let newborn = model.newborn_state();
+        let mut state = model.initial_state(initial)?.iter()
+            .map(|x| u64::try_from(x).map_err(|_| "too many lanternfish to simulate".to_string()))
+            .collect::<Result<Vec<u64>, String>>()?;
+
+        let mut totals = Vec::with_capacity(days as usize + 1);
+        totals.push(total(&state)?);
+        for _ in 0..days {
+            let mut next = vec![0u64; state.len()];
+            for (idx, count) in state.iter().copied().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                let (next_idx, spawns) = successors[idx];
+                if spawns {
+                    let births = sample(count, self.spawn_probability, rng);
+                    next[newborn] = next[newborn].checked_add(births).ok_or("lanternfish population overflowed")?;
+                }
+                if let Some(next_idx) = next_idx {
+                    let survivors = sample(count, 1.0 - self.mortality, rng);
+                    next[next_idx] = next[next_idx].checked_add(survivors).ok_or("lanternfish population overflowed")?;
+                }
+            }
+            state = next;
+            totals.push(total(&state)?);
+        }
+        Ok(totals)
+    }
+
+    // Runs independent replicates spread over every available core. Each replicate gets its own RNG
+    // seeded from `seed` and its index, so the results don't depend on how many threads there are.
+    pub fn replicates(&self, model: &Model, initial: &[u64], days: u64, replicates: usize, seed: u64) -> Result<Vec<Vec<u64>>, String> {
+        let threads = std::thread::available_parallelism().map(|x| x.get()).unwrap_or(1).min(replicates.max(1));
+        let chunk = replicates.div_ceil(threads).max(1);
+        let indexes = (0..replicates).collect::<Vec<usize>>();
+
+        std::thread::scope(|scope| {
+            let handles = indexes.chunks(chunk)
+                .map(|idxs| scope.spawn(move || {
+                    idxs.iter()
+                        .map(|idx| {
+                            let mut rng = StdRng::seed_from_u64(seed.wrapping_add((*idx as u64).wrapping_mul(0x9e3779b97f4a7c15)));
+                            self.simulate(model, initial, days, &mut rng)
+                        })
+                        .collect::<Result<Vec<Vec<u64>>, String>>()
+                }))
+                .collect::<Vec<_>>();
+            let mut runs = Vec::with_capacity(replicates);
+            for handle in handles {
+                runs.extend(handle.join().map_err(|_| "simulation thread panicked".to_string())??);
+            }
+            Ok(runs)
+        })
+    }
+}
+
+fn sample<R>(n: u64, p: f64, rng: &mut R) -> u64 where R: Rng {
+    Binomial::new(n, p).expect("probability checked in Stochastic::new").sample(rng)
+}
+
+fn total(state: &[u64]) -> Result<u64, String> {
+    state.iter().try_fold(0u64, |acc, x| acc.checked_add(*x)).ok_or_else(|| "lanternfish population overflowed".to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaySummary {
+    pub day: u64,
+    pub mean: f64,
+    // In the same order as the percentiles asked for.
+    pub percentiles: Vec<u64>,
+    // What the model says without any randomness.
+    pub deterministic: BigUint,
+}
+
+// Mean and (nearest-rank) percentiles of the replicate populations for each day, next to the
+// deterministic cohort model's count.
+pub fn summarise(model: &Model, initial: &[u64], runs: &[Vec<u64>], percentiles: &[f64]) -> Result<Vec<DaySummary>, String> {
+    let days = runs.iter().map(|x| x.len()).min().unwrap_or(0);
+    let deterministic = model.series(initial, days.saturating_sub(1) as u64)?.totals();
+
+    Ok((0..days).map(|day| {
+        let mut populations = runs.iter().map(|x| x[day]).collect::<Vec<u64>>();
+        populations.sort_unstable();
+        let mean = populations.iter().map(|x| *x as f64).sum::<f64>() / populations.len() as f64;
+        let percentiles = percentiles.iter()
+            .map(|p| {
+                let rank = ((p / 100.0) * populations.len() as f64).ceil() as usize;
+                populations[rank.clamp(1, populations.len()) - 1]
+            })
+            .collect();
+        DaySummary { day: day as u64, mean, percentiles, deterministic: deterministic[day].clone() }
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::stochastic::{summarise, Stochastic};
+    use crate::Model;
+
+    #[test]
+    fn certain_fish_match_the_model() {
+        let model = Model::default();
+        let initial = model.parse_cohorts("3,4,3,1,2").unwrap();
+        let runs = Stochastic::new(1.0, 0.0).unwrap().replicates(&model, &initial, 80, 5, 1).unwrap();
+        let summary = summarise(&model, &initial, &runs, &[50.0]).unwrap();
+        assert_eq!(summary.len(), 81);
+        for day in summary {
+            assert_eq!(BigUint::from(day.percentiles[0]), day.deterministic);
+            assert_eq!(BigUint::from(day.mean as u64), day.deterministic);
+        }
+    }
+
+    #[test]
+    fn seeded_and_plausible() {
+        let model = Model::new(7, 2, Some(40)).unwrap();
+        let initial = model.parse_cohorts(&vec!["3"; 1000].join(",")).unwrap();
+        let stochastic = Stochastic::new(0.5, 0.01).unwrap();
+        let runs = stochastic.replicates(&model, &initial, 60, 40, 7).unwrap();
+        assert_eq!(runs, stochastic.replicates(&model, &initial, 60, 40, 7).unwrap());
+        assert_ne!(runs, stochastic.replicates(&model, &initial, 60, 40, 8).unwrap());
+
+        let mut rng = StdRng::seed_from_u64(3);
+        assert_eq!(stochastic.simulate(&model, &initial, 60, &mut rng).unwrap().len(), 61);
+
+        let summary = summarise(&model, &initial, &runs, &[5.0, 50.0, 95.0]).unwrap();
+        let last = summary.last().unwrap();
+        assert!(last.percentiles[0] <= last.percentiles[1] && last.percentiles[1] <= last.percentiles[2]);
+        assert!(BigUint::from(last.percentiles[2]) < last.deterministic);
+        // Half the spawns and a few deaths still leave the population growing.
+        assert!(last.mean > 1000.0);
+    }
+
+    #[test]
+    fn bad_parameters() {
+        assert!(Stochastic::new(1.5, 0.0).is_err());
+        assert!(Stochastic::new(0.5, -0.1).is_err());
+        assert!(Stochastic::new(f64::NAN, 0.0).is_err());
+    }
+}
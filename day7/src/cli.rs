@@ -0,0 +1,39 @@
+use std::io::BufRead;
+
+use crate::fuel::{cost_curve, parse_fuel_cost, FuelCost};
+use crate::parse_positions;
+
+// The day7 binaries pass in their own fuel cost, which --cost overrides (see fuel::parse_fuel_cost).
+// --curve prints the total cost of every position as CSV and --targets=K lines the crabs up on K
+// positions instead of one.
+pub fn main(default_cost: &dyn FuelCost) -> Result<(), String> {
+    let stdin = std::io::stdin();
+
+    let line = stdin.lock().lines().next().ok_or("no crabs")?.map_err(|e| e.to_string())?;
+    let positions = parse_positions(&line)?;
+
+    let cost = args::value::<String>("cost")?.map(|x| parse_fuel_cost(&x)).transpose()?;
+    let cost = cost.as_deref().unwrap_or(default_cost);
+
+    if std::env::args().any(|x| x == "--curve") {
+        println!("position,cost");
+        for point in cost_curve(&positions, cost) {
+            println!("{},{}", point.position, point.cost);
+        }
+        return Ok(());
+    }
+
+    if let Some(k) = args::value::<usize>("targets")? {
+        let cheapest = cost.align_many(&positions, k)?.ok_or("no crabs or no targets")?;
+        println!("cheapest moves are to {}, costing {}",
+            cheapest.targets.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", "), cheapest.cost);
+        for (idx, target) in cheapest.targets.iter().enumerate() {
+            println!("{}: {} crabs", target, cheapest.assignments.iter().filter(|x| **x == idx).count());
+        }
+        return Ok(());
+    }
+
+    let cheapest = cost.align(&positions).ok_or("no crabs")?;
+    println!("cheapest move is to {}, costing {}", cheapest.position, cheapest.cost);
+    Ok(())
+}
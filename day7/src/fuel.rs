@@ -0,0 +1,249 @@
+use std::str::FromStr;
+
+use crate::multi::{self, MultiAlignment};
+use crate::{align_linear, align_triangular, Alignment};
+
+// How much fuel a crab burns to move a given distance. The cost should never go down as the distance
+// goes up, so the cheapest position is always somewhere between the outermost crabs.
+pub trait FuelCost {
+    fn cost(&self, distance: u128) -> u128;
+
+    // Whether the cost grows at a rate that never goes down. The total over all crabs is then convex
+    // in the target position, which lets align search for the bottom instead of trying everything.
+    fn is_convex(&self) -> bool {
+        false
+    }
+
+    fn align(&self, positions: &[i64]) -> Option<Alignment> {
+        search(positions, self)
+    }
+
+    // Up to k targets, see the multi module. Only linear and triangular costs can do this.
+    fn align_many(&self, _positions: &[i64], _k: usize) -> Result<Option<MultiAlignment>, String> {
+        Err("multi-point alignment needs a linear or triangular fuel cost".to_string())
+    }
+}
+
+impl<F> FuelCost for F where F: Fn(u128) -> u128 {
+    fn cost(&self, distance: u128) -> u128 {
+        self(distance)
+    }
+}
+
+// One unit of fuel per step, as in part 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: u128) -> u128 {
+        distance
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn align(&self, positions: &[i64]) -> Option<Alignment> {
+        align_linear(positions)
+    }
+
+    fn align_many(&self, positions: &[i64], k: usize) -> Result<Option<MultiAlignment>, String> {
+        Ok(multi::align_linear(positions, k))
+    }
+}
+
+// Each step costs one more than the last, as in part 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: u128) -> u128 {
+        crate::triangular(distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn align(&self, positions: &[i64]) -> Option<Alignment> {
+        align_triangular(positions)
+    }
+
+    fn align_many(&self, positions: &[i64], k: usize) -> Result<Option<MultiAlignment>, String> {
+        Ok(multi::align_triangular(positions, k))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: u128) -> u128 {
+        distance * distance
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+// Fuel per step that changes with how far the crab has already come: each band charges its rate for
+// every step up to its limit, and steps past the last band are charged at `beyond`. Parsed from e.g.
+// "1:3,2:10,5", meaning 1 per step for the first 3 steps, 2 per step up to 10 steps and 5 after that.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piecewise {
+    bands: Vec<(u128, u128)>,
+    beyond: u128,
+}
+
+impl Piecewise {
+    pub fn new(bands: Vec<(u128, u128)>, beyond: u128) -> Result<Piecewise, String> {
+        if bands.windows(2).any(|x| x[0].1 >= x[1].1) || bands.first().is_some_and(|x| x.1 == 0) {
+            return Err("piecewise cost band limits must be increasing and above 0".to_string());
+        }
+        Ok(Piecewise { bands, beyond })
+    }
+}
+
+impl FuelCost for Piecewise {
+    fn cost(&self, distance: u128) -> u128 {
+        let mut cost = 0;
+        let mut from = 0;
+        for (rate, until) in self.bands.iter().copied() {
+            cost += rate * (distance.min(until) - from.min(distance));
+            from = until;
+        }
+        cost + self.beyond * distance.saturating_sub(from)
+    }
+
+    fn is_convex(&self) -> bool {
+        let rates = self.bands.iter().map(|x| x.0).chain([self.beyond]).collect::<Vec<u128>>();
+        rates.windows(2).all(|x| x[0] <= x[1])
+    }
+}
+
+impl FromStr for Piecewise {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split(',').map(|x| x.trim()).collect::<Vec<&str>>();
+        let (beyond, bands) = parts.split_last().ok_or("no piecewise cost bands")?;
+        let bands = bands.iter()
+            .map(|x| {
+                let (rate, until) = x.split_once(':').ok_or_else(|| format!("piecewise cost band '{}' should be rate:limit", x))?;
+                Ok((
+                    rate.parse::<u128>().map_err(|e| format!("piecewise cost rate '{}': {}", rate, e))?,
+                    until.parse::<u128>().map_err(|e| format!("piecewise cost limit '{}': {}", until, e))?,
+                ))
+            })
+            .collect::<Result<Vec<(u128, u128)>, String>>()?;
+        let beyond = beyond.parse::<u128>().map_err(|e| format!("piecewise cost rate '{}': {}", beyond, e))?;
+        Piecewise::new(bands, beyond)
+    }
+}
+
+// "linear", "triangular", "quadratic" or "piecewise:" followed by the bands.
+pub fn parse_fuel_cost(s: &str) -> Result<Box<dyn FuelCost>, String> {
+    match s {
+        "linear" => Ok(Box::new(Linear)),
+        "triangular" => Ok(Box::new(Triangular)),
+        "quadratic" => Ok(Box::new(Quadratic)),
+        _ => match s.strip_prefix("piecewise:") {
+            Some(bands) => Ok(Box::new(bands.parse::<Piecewise>()?)),
+            None => Err(format!("unknown fuel cost '{}', expected linear, triangular, quadratic or piecewise:BANDS", s)),
+        },
+    }
+}
+
+pub fn total_cost<F>(positions: &[i64], target: i64, fuel: &F) -> u128 where F: FuelCost + ?Sized {
+    positions.iter().map(|x| fuel.cost((*x as i128 - target as i128).unsigned_abs())).sum()
+}
+
+// Total fuel for every position from the leftmost crab to the rightmost.
+pub fn cost_curve<F>(positions: &[i64], fuel: &F) -> Vec<Alignment> where F: FuelCost + ?Sized {
+    match (positions.iter().min(), positions.iter().max()) {
+        (Some(min), Some(max)) => (*min..=*max)
+            .map(|position| Alignment { position, cost: total_cost(positions, position, fuel) })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// Ternary search for convex costs, done as a binary search for the first position where moving one
+// further right stops getting cheaper, so the leftmost of several equally cheap positions wins just
+// like it does when trying every position.
+pub fn search<F>(positions: &[i64], fuel: &F) -> Option<Alignment> where F: FuelCost + ?Sized {
+    if !fuel.is_convex() {
+        return cost_curve(positions, fuel).into_iter().min_by_key(|x| (x.cost, x.position));
+    }
+    let (mut lo, mut hi) = (*positions.iter().min()?, *positions.iter().max()?);
+    while lo < hi {
+        let mid = lo + ((hi as i128 - lo as i128) / 2) as i64;
+        if total_cost(positions, mid + 1, fuel) < total_cost(positions, mid, fuel) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(Alignment { position: lo, cost: total_cost(positions, lo, fuel) })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::fuel::{cost_curve, parse_fuel_cost, search, FuelCost, Linear, Piecewise, Quadratic, Triangular};
+    use crate::{parse_positions, Alignment};
+
+    fn exhaustive(positions: &[i64], fuel: &dyn FuelCost) -> Alignment {
+        cost_curve(positions, fuel).into_iter().min_by_key(|x| (x.cost, x.position)).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let positions = parse_positions("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(Linear.align(&positions), Some(Alignment { position: 2, cost: 37 }));
+        assert_eq!(search(&positions, &Triangular), Some(Alignment { position: 5, cost: 168 }));
+        assert_eq!(Quadratic.align(&positions).unwrap().position, 5);
+        assert_eq!(cost_curve(&positions, &Linear).len(), 17);
+        assert_eq!(cost_curve(&positions, &Linear)[2], Alignment { position: 2, cost: 37 });
+    }
+
+    #[test]
+    fn piecewise() {
+        let fuel = "1:3,2:10,5".parse::<Piecewise>().unwrap();
+        assert_eq!((0..13).map(|x| fuel.cost(x)).collect::<Vec<u128>>(), vec![0, 1, 2, 3, 5, 7, 9, 11, 13, 15, 17, 22, 27]);
+        assert!(fuel.is_convex());
+        assert!(!"5:2,1".parse::<Piecewise>().unwrap().is_convex());
+        assert!("1:3,2:3,5".parse::<Piecewise>().is_err());
+        assert!("1:3,x".parse::<Piecewise>().is_err());
+        assert!(parse_fuel_cost("piecewise:1:3,2").is_ok());
+        assert!(parse_fuel_cost("cubic").is_err());
+    }
+
+    #[test]
+    fn matches_exhaustive() {
+        let mut rng = StdRng::seed_from_u64(0x2545f4914f6cdd1d);
+        let mut rand = |n: u64| rng.gen_range(0..n);
+        let fuels: Vec<Box<dyn FuelCost>> = vec![
+            Box::new(Linear),
+            Box::new(Triangular),
+            Box::new(Quadratic),
+            Box::new("1:3,2:10,5".parse::<Piecewise>().unwrap()),
+            Box::new("5:2,1".parse::<Piecewise>().unwrap()),
+            // Costs the same to go anywhere within 4 steps, which makes for flat stretches to search.
+            Box::new(|x: u128| x / 4),
+        ];
+        for _ in 0..300 {
+            let spread = 1 + rand(100);
+            let positions = (0..1 + rand(20)).map(|_| rand(spread) as i64 - 30).collect::<Vec<i64>>();
+            for fuel in fuels.iter() {
+                assert_eq!(fuel.align(&positions).unwrap(), exhaustive(&positions, fuel.as_ref()));
+            }
+            // Linear and Triangular have closed forms of their own, so check the search on them too.
+            assert_eq!(search(&positions, &Linear).unwrap(), exhaustive(&positions, &Linear));
+            assert_eq!(search(&positions, &Triangular).unwrap(), exhaustive(&positions, &Triangular));
+        }
+    }
+}
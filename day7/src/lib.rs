@@ -0,0 +1,105 @@
+pub mod cli;
+pub mod fuel;
+pub mod multi;
+
+pub fn parse_positions(s: &str) -> Result<Vec<i64>, String> {
+    s.trim()
+        .split(',')
+        .map(|x| x.trim().parse::<i64>().map_err(|e| format!("crab position '{}': {}", x, e)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i64,
+    pub cost: u128,
+}
+
+fn distance(a: i64, b: i64) -> u128 {
+    (a as i128 - b as i128).unsigned_abs()
+}
+
+pub fn linear_cost(positions: &[i64], target: i64) -> u128 {
+    positions.iter().map(|x| distance(*x, target)).sum()
+}
+
+// 1 + 2 + ... + distance.
+pub fn triangular(distance: u128) -> u128 {
+    distance * (distance + 1) / 2
+}
+
+pub fn triangular_cost(positions: &[i64], target: i64) -> u128 {
+    positions.iter().map(|x| triangular(distance(*x, target))).sum()
+}
+
+// The median minimises the sum of distances. With an even number of crabs anywhere between the two
+// middle ones does, and the lower one is picked.
+pub fn align_linear(positions: &[i64]) -> Option<Alignment> {
+    if positions.is_empty() {
+        return None;
+    }
+    let mut positions = positions.to_vec();
+    let mid = (positions.len() - 1) / 2;
+    let (_, median, _) = positions.select_nth_unstable(mid);
+    let position = *median;
+    Some(Alignment { position, cost: linear_cost(&positions, position) })
+}
+
+// Triangular cost is (d² + d) / 2, and the real-valued optimum of that lies within half a step of the
+// mean. The cost is strictly convex, so checking the integers around the mean is enough.
+pub fn align_triangular(positions: &[i64]) -> Option<Alignment> {
+    if positions.is_empty() {
+        return None;
+    }
+    let sum = positions.iter().map(|x| *x as i128).sum::<i128>();
+    let n = positions.len() as i128;
+    let floor = sum.div_euclid(n) as i64;
+    (floor.saturating_sub(1)..=floor.saturating_add(2))
+        .map(|position| Alignment { position, cost: triangular_cost(positions, position) })
+        .min_by_key(|x| (x.cost, x.position))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::{align_linear, align_triangular, linear_cost, parse_positions, triangular_cost, Alignment};
+
+    #[test]
+    fn example() {
+        let positions = parse_positions("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(align_linear(&positions), Some(Alignment { position: 2, cost: 37 }));
+        assert_eq!(align_triangular(&positions), Some(Alignment { position: 5, cost: 168 }));
+        assert_eq!(triangular_cost(&positions, 2), 206);
+    }
+
+    fn brute_force<F>(positions: &[i64], cost: F) -> Alignment where F: Fn(&[i64], i64) -> u128 {
+        let (min, max) = (*positions.iter().min().unwrap(), *positions.iter().max().unwrap());
+        (min..=max)
+            .map(|position| Alignment { position, cost: cost(positions, position) })
+            .min_by_key(|x| (x.cost, x.position))
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0x853c49e6748fea9b);
+        let mut rand = |n: u64| rng.gen_range(0..n);
+        for _ in 0..500 {
+            let spread = 1 + rand(200);
+            let positions = (0..1 + rand(30)).map(|_| rand(spread) as i64 - 50).collect::<Vec<i64>>();
+            assert_eq!(align_linear(&positions).unwrap(), brute_force(&positions, linear_cost));
+            assert_eq!(align_triangular(&positions).unwrap(), brute_force(&positions, triangular_cost));
+        }
+    }
+
+    #[test]
+    fn wide_inputs() {
+        let positions = vec![-4_000_000_000_000, 4_000_000_000_000, 7];
+        assert_eq!(align_linear(&positions), Some(Alignment { position: 7, cost: 8_000_000_000_000 }));
+        assert_eq!(align_triangular(&positions).unwrap().position, 2);
+        assert_eq!(align_linear(&[]), None);
+        assert!(parse_positions("1,x").is_err());
+    }
+}
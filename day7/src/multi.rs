@@ -0,0 +1,208 @@
+// Several positions for the crabs to line up on, with each crab going to whichever is cheapest for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiAlignment {
+    // In ascending order.
+    pub targets: Vec<i64>,
+    // For each crab in input order, the index of the target it goes to.
+    pub assignments: Vec<usize>,
+    pub cost: u128,
+}
+
+// The distinct crab positions in order, with running totals of how many crabs there are up to each one
+// and of their positions and squared positions, so any run of them can be costed without going through
+// every crab.
+struct Prefix {
+    values: Vec<i64>,
+    counts: Vec<i128>,
+    sums: Vec<i128>,
+    squares: Vec<i128>,
+}
+
+impl Prefix {
+    fn new(positions: &[i64]) -> Prefix {
+        let mut sorted = positions.to_vec();
+        sorted.sort_unstable();
+        let mut prefix = Prefix { values: vec![], counts: vec![0], sums: vec![0], squares: vec![0] };
+        for x in sorted {
+            if prefix.values.last() != Some(&x) {
+                prefix.values.push(x);
+                prefix.counts.push(*prefix.counts.last().unwrap());
+                prefix.sums.push(*prefix.sums.last().unwrap());
+                prefix.squares.push(*prefix.squares.last().unwrap());
+            }
+            let x = x as i128;
+            *prefix.counts.last_mut().unwrap() += 1;
+            *prefix.sums.last_mut().unwrap() += x;
+            *prefix.squares.last_mut().unwrap() += x * x;
+        }
+        prefix
+    }
+
+    fn count(&self, lo: usize, hi: usize) -> i128 {
+        self.counts[hi] - self.counts[lo]
+    }
+
+    fn sum(&self, lo: usize, hi: usize) -> i128 {
+        self.sums[hi] - self.sums[lo]
+    }
+
+    // Total distance from the crabs at values[lo..hi] to t.
+    fn distance(&self, lo: usize, hi: usize, t: i64) -> u128 {
+        let split = lo + self.values[lo..hi].partition_point(|x| *x < t);
+        let t = t as i128;
+        let below = self.count(lo, split) * t - self.sum(lo, split);
+        let above = self.sum(split, hi) - self.count(split, hi) * t;
+        (below + above) as u128
+    }
+
+    // Total squared distance from the crabs at values[lo..hi] to t.
+    fn squared(&self, lo: usize, hi: usize, t: i64) -> u128 {
+        let t = t as i128;
+        (self.squares[hi] - self.squares[lo] - 2 * t * self.sum(lo, hi) + self.count(lo, hi) * t * t) as u128
+    }
+
+    // Lower median, as in align_linear.
+    fn linear(&self, lo: usize, hi: usize) -> (i64, u128) {
+        let mid = (self.count(lo, hi) - 1) / 2;
+        let t = self.values[lo + self.counts[lo + 1..=hi].partition_point(|x| x - self.counts[lo] <= mid)];
+        (t, self.distance(lo, hi, t))
+    }
+
+    // Around the mean, as in align_triangular, using (d² + d) / 2 summed over the crabs.
+    fn triangular(&self, lo: usize, hi: usize) -> (i64, u128) {
+        let floor = self.sum(lo, hi).div_euclid(self.count(lo, hi)) as i64;
+        (floor.saturating_sub(1)..=floor.saturating_add(2))
+            .map(|t| t.clamp(self.values[lo], self.values[hi - 1]))
+            .map(|t| (t, (self.squared(lo, hi, t) + self.distance(lo, hi, t)) / 2))
+            .min_by_key(|(t, cost)| (*cost, *t))
+            .unwrap()
+    }
+}
+
+// With every crab going to its nearest target, the crabs sharing a target are a run of neighbours in
+// sorted order. best[g][j] is the cheapest way to split the first j distinct positions into g runs,
+// trying every place for the last run to start.
+fn align<F>(positions: &[i64], k: usize, segment: F) -> Option<MultiAlignment> where F: Fn(&Prefix, usize, usize) -> (i64, u128) {
+    if positions.is_empty() || k == 0 {
+        return None;
+    }
+    let prefix = Prefix::new(positions);
+    let n = prefix.values.len();
+    let k = k.min(n);
+
+    let mut best = vec![vec![None; n + 1]; k + 1];
+    let mut start = vec![vec![0; n + 1]; k + 1];
+    best[0][0] = Some(0u128);
+    for g in 1..=k {
+        for j in g..=n {
+            for i in g - 1..j {
+                if let Some(before) = best[g - 1][i] {
+                    let cost = before + segment(&prefix, i, j).1;
+                    if best[g][j].is_none_or(|x| cost < x) {
+                        best[g][j] = Some(cost);
+                        start[g][j] = i;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut targets = Vec::with_capacity(k);
+    let mut j = n;
+    for g in (1..=k).rev() {
+        let i = start[g][j];
+        targets.push(segment(&prefix, i, j).0);
+        j = i;
+    }
+    targets.reverse();
+
+    let assignments = positions.iter()
+        .map(|x| {
+            let idx = targets.partition_point(|t| t < x);
+            match idx {
+                0 => 0,
+                _ if idx == targets.len() => idx - 1,
+                _ if x - targets[idx - 1] <= targets[idx] - x => idx - 1,
+                _ => idx,
+            }
+        })
+        .collect();
+    Some(MultiAlignment { targets, assignments, cost: best[k][n].unwrap() })
+}
+
+// At most k targets (fewer if there are fewer distinct crab positions) with linear fuel cost.
+pub fn align_linear(positions: &[i64], k: usize) -> Option<MultiAlignment> {
+    align(positions, k, Prefix::linear)
+}
+
+// At most k targets with triangular fuel cost.
+pub fn align_triangular(positions: &[i64], k: usize) -> Option<MultiAlignment> {
+    align(positions, k, Prefix::triangular)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::multi::{align_linear, align_triangular, MultiAlignment};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::{parse_positions, triangular};
+
+    fn brute_force<F>(positions: &[i64], k: usize, cost: F) -> u128 where F: Fn(u128) -> u128 {
+        let (min, max) = (*positions.iter().min().unwrap(), *positions.iter().max().unwrap());
+        let mut best = u128::MAX;
+        let mut targets = vec![min; k];
+        loop {
+            best = best.min(positions.iter()
+                .map(|x| targets.iter().map(|t| cost((x - t).unsigned_abs() as u128)).min().unwrap())
+                .sum());
+            match targets.iter().position(|t| *t < max) {
+                Some(idx) => {
+                    targets[idx] += 1;
+                    targets[..idx].iter_mut().for_each(|t| *t = min);
+                }
+                None => return best,
+            }
+        }
+    }
+
+    fn check<F>(positions: &[i64], alignment: &MultiAlignment, cost: F) where F: Fn(u128) -> u128 {
+        assert!(alignment.targets.windows(2).all(|x| x[0] < x[1]));
+        let total = positions.iter().zip(alignment.assignments.iter())
+            .map(|(x, idx)| cost((x - alignment.targets[*idx]).unsigned_abs() as u128))
+            .sum::<u128>();
+        assert_eq!(total, alignment.cost);
+    }
+
+    #[test]
+    fn example() {
+        let positions = parse_positions("16,1,2,0,4,2,7,1,2,14").unwrap();
+        let one = align_linear(&positions, 1).unwrap();
+        assert_eq!((one.targets, one.cost), (vec![2], 37));
+        assert_eq!(align_triangular(&positions, 1).unwrap().cost, 168);
+
+        let two = align_linear(&positions, 2).unwrap();
+        assert_eq!(two.targets, vec![2, 14]);
+        assert_eq!(two.assignments, vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(two.cost, 13);
+        assert_eq!(align_linear(&positions, 10).unwrap().cost, 0);
+        assert_eq!(align_linear(&positions, 0), None);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0x9e3779b97f4a7c15);
+        let mut rand = |n: u64| rng.gen_range(0..n);
+        for _ in 0..150 {
+            let spread = 1 + rand(25);
+            let positions = (0..1 + rand(12)).map(|_| rand(spread) as i64 - 10).collect::<Vec<i64>>();
+            let k = 1 + rand(3) as usize;
+            let linear = align_linear(&positions, k).unwrap();
+            check(&positions, &linear, |x| x);
+            assert_eq!(linear.cost, brute_force(&positions, k, |x| x));
+            let tri = align_triangular(&positions, k).unwrap();
+            check(&positions, &tri, triangular);
+            assert_eq!(tri.cost, brute_force(&positions, k, triangular));
+        }
+    }
+}
@@ -1,34 +1,22 @@
-use std::collections::HashMap;
 use std::io::BufRead;
-use std::iter::Map;
 
-fn main() {
-    let stdin = std::io::stdin();
+use day8::Entry;
 
-    let input: Vec<(Vec<String>, Vec<String>)> = stdin.lock().lines()
-        .map(|line| {
-            let line = line.unwrap();
-            let mut it = line.split(" | ");
-            (
-                it.next().unwrap().split(" ").map(|x| x.to_string()).collect(),
-                it.next().unwrap().split(" ").map(|x| x.to_string()).collect()
-            )
-        })
-        .collect();
+fn main() -> Result<(), String> {
+    let stdin = std::io::stdin();
+    let alphabet = day8::cli::alphabet()?;
 
     let mut uniq_digit_count = 0;
 
-    for (_, output) in input {
-        for str in &output {
-            match str.len() {
-                2|3|4|7 => {
-                    println!("uniq: {}", str);
-                    uniq_digit_count += 1;
-                },
-                _ => {}
-            }
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
         }
+        let entry = line.parse::<Entry>()?;
+        uniq_digit_count += entry.outputs.iter().filter(|x| alphabet.unique_length(**x).is_some()).count();
     }
 
     println!("Uniq digit occurrences: {}", uniq_digit_count);
+    Ok(())
 }
@@ -0,0 +1,21 @@
+use crate::Alphabet;
+
+// The alphabet from the definition file given with --alphabet=PATH (see the alphabets directory for
+// some), or the seven segment digits.
+pub fn alphabet() -> Result<Alphabet, String> {
+    match args::value::<String>("alphabet")? {
+        Some(path) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("{}: {}", path, e))?
+            .parse::<Alphabet>()
+            .map_err(|e| format!("{}: {}", path, e)),
+        None => Ok(Alphabet::seven_segment()),
+    }
+}
+
+// How many stuck segments to allow for with --faults=K, and the odds of any one being stuck from
+// --fault-rate (0.01 unless given). None without --faults.
+pub fn fault_tolerance() -> Result<Option<(usize, f64)>, String> {
+    let faults = args::value::<usize>("faults")?;
+    let rate = args::value::<f64>("fault-rate")?;
+    Ok(faults.map(|x| (x, rate.unwrap_or(0.01))))
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{observed, wirings, Alphabet, Decoded, Entry, Mapping, Pattern};
+
+// More wirings than this fitting one set of faults means the entry doesn't say much about the wiring
+// at all, and the rest are left out of the confidence.
+const WIRING_LIMIT: usize = 1000;
+
+// A display segment that stays lit, or dark, whatever it's sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fault {
+    pub segment: usize,
+    pub stuck_on: bool,
+}
+
+impl Fault {
+    fn render(faults: &[Fault], pattern: Pattern) -> Pattern {
+        faults.iter().fold(pattern, |acc, x| if x.stuck_on { acc | 1 << x.segment } else { acc & !(1 << x.segment) })
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} stuck {}", (b'a' + self.segment as u8) as char, if self.stuck_on { "on" } else { "off" })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    pub decoded: Decoded,
+    // The fewest faults that explain the entry with this reading.
+    pub faults: Vec<Fault>,
+    // How much of the weight of every explanation found goes to this reading of the outputs.
+    pub confidence: f64,
+}
+
+// Every set of at most `max` faults, fewest first.
+fn fault_sets(segments: usize, max: usize) -> Vec<Vec<Fault>> {
+    let mut sets = vec![vec![]];
+    let mut last = vec![vec![]];
+    for _ in 0..max.min(segments) {
+        last = last.iter()
+            .flat_map(|set: &Vec<Fault>| {
+                let from = set.last().map_or(0, |x| x.segment + 1);
+                (from..segments).flat_map(move |segment| [false, true].map(|stuck_on| {
+                    let mut set = set.clone();
+                    set.push(Fault { segment, stuck_on });
+                    set
+                }))
+            })
+            .collect();
+        sets.extend(last.iter().cloned());
+    }
+    sets
+}
+
+// Decodes an entry from a display with up to `max_faults` stuck segments. Every set of faults is tried
+// against every wiring, each explanation that fits being weighted by `fault_rate` to the power of how
+// many faults it needs. Faults can make glyphs look the same, in which case an output could be any of
+// them and each reading counts separately. The reading with the most weight wins.
+pub fn decode_faulty(alphabet: &Alphabet, entry: &Entry, max_faults: usize, fault_rate: f64) -> Result<Diagnosis, String> {
+    if !(fault_rate > 0.0 && fault_rate < 1.0) {
+        return Err(format!("fault rate must be between 0 and 1, got {}", fault_rate));
+    }
+    let patterns = observed(alphabet, entry)?;
+
+    // Each reading's total weight, and its likeliest explanation.
+    let mut readings: HashMap<Vec<usize>, (f64, Mapping, Vec<Fault>)> = HashMap::new();
+    let mut order = vec![];
+    let mut total = 0.0;
+    for faults in fault_sets(alphabet.segments(), max_faults) {
+        let weight = fault_rate.powi(faults.len() as i32);
+        let glyphs = alphabet.glyphs().iter().map(|x| Fault::render(&faults, x.pattern)).collect::<Vec<Pattern>>();
+        for mapping in wirings(alphabet.segments(), &glyphs, &patterns, WIRING_LIMIT).unwrap_or_default() {
+            let matching = |p: Pattern| {
+                let shown = mapping.apply(p);
+                glyphs.iter().enumerate().filter(|(_, x)| **x == shown).map(|(idx, _)| idx).collect::<Vec<usize>>()
+            };
+            // Every signal is a different glyph, so there have to be enough glyphs that look the same
+            // for all the signals that do.
+            if entry.signals.iter().any(|p| entry.signals.iter().filter(|x| *x == p).count() > matching(*p).len()) {
+                continue;
+            }
+
+            let mut choices = vec![vec![]];
+            for p in entry.outputs.iter() {
+                let options = matching(*p);
+                choices = choices.into_iter()
+                    .flat_map(|x: Vec<usize>| options.iter().map(move |y| x.iter().copied().chain([*y]).collect()))
+                    .collect();
+            }
+            for glyphs in choices {
+                total += weight;
+                match readings.get_mut(&glyphs) {
+                    Some(reading) => reading.0 += weight,
+                    None => {
+                        order.push(glyphs.clone());
+                        readings.insert(glyphs, (weight, mapping.clone(), faults.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    // Ties go to whichever reading turned up first, so to the one needing the fewest faults.
+    let best = order.into_iter()
+        .reduce(|a, b| if readings[&b].0 > readings[&a].0 { b } else { a })
+        .ok_or_else(|| format!("no wiring fits every pattern with up to {} faults", max_faults))?;
+    let (weight, mapping, faults) = readings.remove(&best).unwrap();
+    Ok(Diagnosis { decoded: Decoded { mapping, glyphs: best }, faults, confidence: weight / total })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faults::{decode_faulty, fault_sets, Fault};
+    use crate::{decode, Alphabet, Entry, Mapping, Pattern};
+
+    // The example entry's wiring, showing the given outputs through a display with these faults.
+    fn faulty_entry(faults: &[Fault], outputs: &[usize]) -> Entry {
+        let alphabet = Alphabet::seven_segment();
+        let wiring = Mapping { wires: vec![3, 4, 0, 5, 6, 1, 2] };
+        let show = |x: Pattern| wiring.apply(Fault::render(faults, x));
+        Entry {
+            signals: alphabet.glyphs().iter().map(|x| show(x.pattern)).collect(),
+            outputs: outputs.iter().map(|x| show(alphabet.glyphs()[*x].pattern)).collect(),
+        }
+    }
+
+    #[test]
+    fn no_faults() {
+        let alphabet = Alphabet::seven_segment();
+        let entry = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf".parse::<Entry>().unwrap();
+        let diagnosis = decode_faulty(&alphabet, &entry, 2, 0.01).unwrap();
+        assert_eq!(diagnosis.decoded, decode(&alphabet, &entry).unwrap());
+        assert_eq!(diagnosis.faults, vec![]);
+        assert!(diagnosis.confidence > 0.99);
+        assert_eq!(fault_sets(7, 2).len(), 1 + 14 + 84);
+    }
+
+    #[test]
+    fn stuck_off() {
+        let alphabet = Alphabet::seven_segment();
+        // Without e, 6 looks like 5 and 8 like 9, but 1 2 3 4 are still all different.
+        let faults = [Fault { segment: 4, stuck_on: false }];
+        let entry = faulty_entry(&faults, &[1, 2, 3, 4]);
+        assert!(decode(&alphabet, &entry).is_err());
+        assert!(decode_faulty(&alphabet, &entry, 0, 0.01).is_err());
+
+        let diagnosis = decode_faulty(&alphabet, &entry, 1, 0.01).unwrap();
+        assert_eq!(diagnosis.decoded.symbols(&alphabet), "1234");
+        assert_eq!(diagnosis.faults, faults);
+        assert_eq!(diagnosis.faults[0].to_string(), "e stuck off");
+        assert!(diagnosis.confidence > 0.9);
+    }
+
+    #[test]
+    fn stuck_on() {
+        let alphabet = Alphabet::seven_segment();
+        // With d always lit a 0 can't be told from an 8.
+        let faults = [Fault { segment: 3, stuck_on: true }];
+        let entry = faulty_entry(&faults, &[0, 7, 1, 1]);
+        let diagnosis = decode_faulty(&alphabet, &entry, 1, 0.01).unwrap();
+        assert_eq!(diagnosis.faults, faults);
+        assert!(diagnosis.decoded.glyphs == vec![0, 7, 1, 1] || diagnosis.decoded.glyphs == vec![8, 7, 1, 1]);
+        assert!((diagnosis.confidence - 0.5).abs() < 0.01);
+
+        assert!(decode_faulty(&alphabet, &entry, 1, 1.5).is_err());
+    }
+}
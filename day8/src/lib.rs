@@ -0,0 +1,435 @@
+pub mod cli;
+pub mod faults;
+
+use std::fmt;
+use std::str::FromStr;
+
+// Lit segments (or live wires) as bits, segment 'a' being bit 0.
+pub type Pattern = u32;
+
+pub const MAX_SEGMENTS: usize = 26;
+
+pub fn parse_pattern(s: &str) -> Result<Pattern, String> {
+    if s.is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    let mut pattern = 0;
+    for char in s.chars() {
+        if !char.is_ascii_lowercase() {
+            return Err(format!("unexpected char in pattern '{}': {}", s, char));
+        }
+        let bit = 1 << (char as u8 - b'a');
+        if pattern & bit != 0 {
+            return Err(format!("segment {} repeated in pattern '{}'", char, s));
+        }
+        pattern |= bit;
+    }
+    Ok(pattern)
+}
+
+pub fn format_pattern(pattern: Pattern) -> String {
+    (0..MAX_SEGMENTS).filter(|x| pattern >> x & 1 == 1).map(|x| (b'a' + x as u8) as char).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    pub symbol: char,
+    pub pattern: Pattern,
+}
+
+// The symbols a display can show and which segments each one lights.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    segments: usize,
+    glyphs: Vec<Glyph>,
+}
+
+impl Alphabet {
+    pub fn new(segments: usize, glyphs: Vec<Glyph>) -> Result<Alphabet, String> {
+        if segments == 0 || segments > MAX_SEGMENTS {
+            return Err(format!("displays must have 1-{} segments, got {}", MAX_SEGMENTS, segments));
+        }
+        for (idx, glyph) in glyphs.iter().enumerate() {
+            if glyph.pattern >> segments != 0 {
+                return Err(format!("glyph {} uses more than {} segments", glyph.symbol, segments));
+            }
+            if let Some(other) = glyphs[..idx].iter().find(|x| x.pattern == glyph.pattern || x.symbol == glyph.symbol) {
+                return Err(format!("glyphs {} and {} clash", other.symbol, glyph.symbol));
+            }
+        }
+        Ok(Alphabet { segments, glyphs })
+    }
+
+    // The usual seven segment digits 0-9.
+    pub fn seven_segment() -> Alphabet {
+        let glyphs = ["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg"].iter()
+            .enumerate()
+            .map(|(idx, x)| Glyph { symbol: (b'0' + idx as u8) as char, pattern: parse_pattern(x).unwrap() })
+            .collect();
+        Alphabet { segments: 7, glyphs }
+    }
+
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    pub fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    pub fn mask(&self) -> Pattern {
+        Pattern::MAX >> (Pattern::BITS as usize - self.segments)
+    }
+
+    // The glyph with this many segments, if only one has that many. Those can be told apart without
+    // knowing the wiring at all.
+    pub fn unique_length(&self, pattern: Pattern) -> Option<usize> {
+        let mut candidates = self.glyphs.iter().enumerate().filter(|(_, x)| x.pattern.count_ones() == pattern.count_ones());
+        match (candidates.next(), candidates.next()) {
+            (Some((idx, _)), None) => Some(idx),
+            _ => None,
+        }
+    }
+}
+
+// One glyph per line, its symbol and then the segments it lights, e.g. "7 acf". A "segments N" line
+// sets how many segments the display has, which is otherwise as many as the glyphs use. Blank lines
+// and lines starting with # are skipped.
+impl FromStr for Alphabet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = None;
+        let mut glyphs = vec![];
+        for (idx, line) in s.lines().enumerate().map(|(idx, x)| (idx + 1, x.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (symbol, pattern) = line.split_once(char::is_whitespace).ok_or_else(|| format!("line {}: expected a symbol and its segments", idx))?;
+            if symbol == "segments" {
+                segments = Some(pattern.trim().parse::<usize>().map_err(|e| format!("line {}: {}", idx, e))?);
+                continue;
+            }
+            let mut chars = symbol.chars();
+            let symbol = match (chars.next(), chars.next()) {
+                (Some(symbol), None) => symbol,
+                _ => return Err(format!("line {}: symbol '{}' should be a single char", idx, symbol)),
+            };
+            let pattern = parse_pattern(pattern.trim()).map_err(|e| format!("line {}: {}", idx, e))?;
+            glyphs.push(Glyph { symbol, pattern });
+        }
+        if glyphs.is_empty() {
+            return Err("no glyphs".to_string());
+        }
+        let used = glyphs.iter().fold(0, |acc, x| acc | x.pattern);
+        Alphabet::new(segments.unwrap_or((Pattern::BITS - used.leading_zeros()) as usize), glyphs)
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::seven_segment()
+    }
+}
+
+// A line of input: the patterns on the scrambled wires for every symbol, and then for the symbols on
+// the display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub signals: Vec<Pattern>,
+    pub outputs: Vec<Pattern>,
+}
+
+impl FromStr for Entry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (signals, outputs) = s.split_once('|').ok_or_else(|| format!("entry '{}' should be signals | outputs", s.trim()))?;
+        Ok(Entry {
+            signals: signals.split_whitespace().map(parse_pattern).collect::<Result<Vec<Pattern>, String>>()?,
+            outputs: outputs.split_whitespace().map(parse_pattern).collect::<Result<Vec<Pattern>, String>>()?,
+        })
+    }
+}
+
+// Which display segment each wire drives, indexed by wire.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mapping {
+    pub wires: Vec<usize>,
+}
+
+impl Mapping {
+    pub fn apply(&self, pattern: Pattern) -> Pattern {
+        self.wires.iter().enumerate().filter(|(wire, _)| pattern >> wire & 1 == 1).fold(0, |acc, (_, segment)| acc | 1 << segment)
+    }
+}
+
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs = self.wires.iter().enumerate()
+            .map(|(wire, segment)| format!("{} -> {}", (b'a' + wire as u8) as char, (b'a' + *segment as u8) as char))
+            .collect::<Vec<String>>();
+        write!(f, "{}", pairs.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub mapping: Mapping,
+    // Indexes into the alphabet's glyphs.
+    pub glyphs: Vec<usize>,
+}
+
+impl Decoded {
+    pub fn symbols(&self, alphabet: &Alphabet) -> String {
+        self.glyphs.iter().map(|x| alphabet.glyphs[*x].symbol).collect()
+    }
+
+    // The outputs read as a number, each glyph being a digit worth its index in the alphabet.
+    pub fn value(&self, alphabet: &Alphabet) -> Option<u64> {
+        let radix = alphabet.glyphs.len() as u64;
+        self.glyphs.iter().try_fold(0u64, |acc, x| acc.checked_mul(radix)?.checked_add(*x as u64))
+    }
+}
+
+// The only wiring that shows every pattern in the entry as a glyph. Entries that fit no wiring or more
+// than one are errors.
+pub fn solve(alphabet: &Alphabet, entry: &Entry) -> Result<Mapping, String> {
+    let glyphs = alphabet.glyphs.iter().map(|x| x.pattern).collect::<Vec<Pattern>>();
+    let mut solutions = wirings(alphabet.segments, &glyphs, &observed(alphabet, entry)?, 2)?;
+    match solutions.len() {
+        0 => Err("no wiring fits every pattern".to_string()),
+        1 => Ok(solutions.pop().unwrap()),
+        _ => Err(format!("ambiguous wiring, both {} and {} fit", solutions[0], solutions[1])),
+    }
+}
+
+// Every distinct pattern in the entry.
+pub(crate) fn observed(alphabet: &Alphabet, entry: &Entry) -> Result<Vec<Pattern>, String> {
+    let mask = alphabet.mask();
+    let mut patterns = entry.signals.iter().chain(entry.outputs.iter()).copied().collect::<Vec<Pattern>>();
+    if let Some(bad) = patterns.iter().find(|x| **x & !mask != 0) {
+        return Err(format!("pattern {} uses more than {} wires", format_pattern(*bad), alphabet.segments));
+    }
+    patterns.sort_unstable();
+    patterns.dedup();
+    Ok(patterns)
+}
+
+// Up to `limit` wirings that show every pattern as one of the glyphs, found by keeping a set of
+// possible segments for every wire. Each pattern can only be one of the glyphs lighting as many
+// segments, so its wires have to drive segments those glyphs light and the other wires segments they
+// don't; narrowing the sets down with that until nothing changes settles most entries, and a search
+// over what's left settles the rest.
+pub(crate) fn wirings(n: usize, glyphs: &[Pattern], patterns: &[Pattern], limit: usize) -> Result<Vec<Mapping>, String> {
+    let mask = Pattern::MAX >> (Pattern::BITS as usize - n);
+    let mut candidates = vec![mask; n];
+    let mut glyphs = patterns.iter()
+        .map(|p| glyphs.iter().copied().filter(|x| x.count_ones() == p.count_ones()).collect::<Vec<Pattern>>())
+        .collect::<Vec<Vec<Pattern>>>();
+    loop {
+        let before = (candidates.clone(), glyphs.iter().map(|x| x.len()).sum::<usize>());
+        for (p, glyphs) in patterns.iter().zip(glyphs.iter_mut()) {
+            glyphs.retain(|g| (0..n).all(|wire| candidates[wire] & if p >> wire & 1 == 1 { *g } else { !*g } != 0));
+            if glyphs.is_empty() {
+                return Err(format!("no wiring shows {} as any glyph", format_pattern(*p)));
+            }
+            let lit = glyphs.iter().fold(0, |acc, x| acc | x);
+            let unlit = glyphs.iter().fold(0, |acc, x| acc | (!x & mask));
+            for (wire, candidates) in candidates.iter_mut().enumerate() {
+                *candidates &= if p >> wire & 1 == 1 { lit } else { unlit };
+            }
+        }
+        for wire in 0..n {
+            if candidates[wire].count_ones() == 1 {
+                let segment = candidates[wire];
+                for (other, candidates) in candidates.iter_mut().enumerate() {
+                    if other != wire {
+                        *candidates &= !segment;
+                    }
+                }
+            }
+        }
+        if candidates.contains(&0) {
+            return Err("no wiring fits every pattern".to_string());
+        }
+        if before == (candidates.clone(), glyphs.iter().map(|x| x.len()).sum::<usize>()) {
+            break;
+        }
+    }
+
+    let search = Search { patterns, glyphs: &glyphs, candidates: &candidates, limit };
+    let mut wiring = vec![None; n];
+    let mut solutions = vec![];
+    search.run(&mut wiring, 0, &mut solutions);
+    Ok(solutions)
+}
+
+struct Search<'a> {
+    patterns: &'a [Pattern],
+    glyphs: &'a [Vec<Pattern>],
+    candidates: &'a [Pattern],
+    limit: usize,
+}
+
+impl Search<'_> {
+    // Whether every pattern could still be one of its glyphs with the wires assigned so far.
+    fn consistent(&self, wiring: &[Option<usize>]) -> bool {
+        self.patterns.iter().zip(self.glyphs.iter()).all(|(p, glyphs)| {
+            let (mut lit, mut unlit) = (0, 0);
+            for (wire, segment) in wiring.iter().enumerate() {
+                if let Some(segment) = segment {
+                    if p >> wire & 1 == 1 {
+                        lit |= 1 << segment;
+                    } else {
+                        unlit |= 1 << segment;
+                    }
+                }
+            }
+            glyphs.iter().any(|g| g & lit == lit && g & unlit == 0)
+        })
+    }
+
+    fn run(&self, wiring: &mut Vec<Option<usize>>, used: Pattern, solutions: &mut Vec<Mapping>) {
+        let next = (0..wiring.len())
+            .filter(|x| wiring[*x].is_none())
+            .min_by_key(|x| (self.candidates[*x] & !used).count_ones());
+        let wire = match next {
+            Some(wire) => wire,
+            None => {
+                solutions.push(Mapping { wires: wiring.iter().map(|x| x.unwrap()).collect() });
+                return;
+            }
+        };
+        let options = self.candidates[wire] & !used;
+        for segment in (0..wiring.len()).filter(|x| options >> x & 1 == 1) {
+            wiring[wire] = Some(segment);
+            if self.consistent(wiring) {
+                self.run(wiring, used | 1 << segment, solutions);
+                if solutions.len() >= self.limit {
+                    break;
+                }
+            }
+        }
+        wiring[wire] = None;
+    }
+}
+
+pub fn decode(alphabet: &Alphabet, entry: &Entry) -> Result<Decoded, String> {
+    let mapping = solve(alphabet, entry)?;
+    let glyphs = entry.outputs.iter()
+        .map(|x| {
+            let pattern = mapping.apply(*x);
+            alphabet.glyphs.iter().position(|g| g.pattern == pattern).ok_or_else(|| format!("{} isn't a glyph", format_pattern(pattern)))
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+    Ok(Decoded { mapping, glyphs })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    use crate::{decode, format_pattern, parse_pattern, solve, Alphabet, Entry, Mapping, Pattern};
+
+    const EXAMPLE: &str = "\
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
+";
+
+    #[test]
+    fn example() {
+        let alphabet = Alphabet::seven_segment();
+        let entries = EXAMPLE.lines().map(|x| x.parse::<Entry>().unwrap()).collect::<Vec<Entry>>();
+        let unique = entries.iter().flat_map(|x| x.outputs.iter()).filter(|x| alphabet.unique_length(**x).is_some()).count();
+        assert_eq!(unique, 26);
+
+        let values = entries.iter().map(|x| decode(&alphabet, x).unwrap().value(&alphabet).unwrap()).collect::<Vec<u64>>();
+        assert_eq!(values, vec![8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315]);
+
+        let entry = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf".parse::<Entry>().unwrap();
+        let decoded = decode(&alphabet, &entry).unwrap();
+        assert_eq!(decoded.mapping, Mapping { wires: vec![2, 5, 6, 0, 1, 3, 4] });
+        assert_eq!(decoded.symbols(&alphabet), "5353");
+    }
+
+    fn scrambled(alphabet: &Alphabet, seed: u64) {
+        let n = alphabet.segments();
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..100 {
+            let mut wires = (0..n).collect::<Vec<usize>>();
+            wires.shuffle(&mut rng);
+            let mapping = Mapping { wires };
+            let mut inverse = vec![0; n];
+            for (wire, segment) in mapping.wires.iter().enumerate() {
+                inverse[*segment] = wire;
+            }
+            let scramble = |x: Pattern| Mapping { wires: inverse.clone() }.apply(x);
+            let mut signals = alphabet.glyphs().iter().map(|x| scramble(x.pattern)).collect::<Vec<Pattern>>();
+            signals.shuffle(&mut rng);
+            let outputs = (0..4).map(|_| rng.gen_range(0..alphabet.glyphs().len())).collect::<Vec<usize>>();
+            let entry = Entry { signals, outputs: outputs.iter().map(|x| scramble(alphabet.glyphs()[*x].pattern)).collect() };
+            let decoded = decode(alphabet, &entry).unwrap();
+            assert_eq!(decoded.mapping, mapping);
+            assert_eq!(decoded.glyphs, outputs);
+        }
+    }
+
+    #[test]
+    fn scrambled_seven_segment() {
+        scrambled(&Alphabet::seven_segment(), 0x5851f42d4c957f2d);
+    }
+
+    #[test]
+    fn alphabets() {
+        let hex = include_str!("../alphabets/hex.txt").parse::<Alphabet>().unwrap();
+        assert_eq!(hex.segments(), 7);
+        assert_eq!(hex.glyphs()[..10], Alphabet::seven_segment().glyphs()[..]);
+        scrambled(&hex, 0x14057b7ef767814f);
+
+        let fourteen = include_str!("../alphabets/fourteen.txt").parse::<Alphabet>().unwrap();
+        assert_eq!(fourteen.segments(), 14);
+        scrambled(&fourteen, 0x2545f4914f6cdd1d);
+
+        // Reads as hex, so a display showing "C0dE" is worth 0xc0de.
+        let entry = "abcefg cf acdeg acdfg bcdf abdfg abdefg acf abcdefg abcdfg abcdef bdefg abeg cdefg abdeg abde | abeg abcefg cdefg abdeg".parse::<Entry>().unwrap();
+        let decoded = decode(&hex, &entry).unwrap();
+        assert_eq!(decoded.symbols(&hex), "C0dE");
+        assert_eq!(decoded.value(&hex), Some(0xc0de));
+
+        let small = "# Just the two\n1 a\nL ab\n".parse::<Alphabet>().unwrap();
+        assert_eq!(small.segments(), 2);
+        assert!("segments 3\n1 a\n2 a\n".parse::<Alphabet>().is_err());
+        assert!("segments 1\n1 ab\n".parse::<Alphabet>().is_err());
+        assert!("10 ab\n".parse::<Alphabet>().is_err());
+        assert!("".parse::<Alphabet>().is_err());
+    }
+
+    #[test]
+    fn bad_entries() {
+        let alphabet = Alphabet::seven_segment();
+        // A lone 1 could be wired either way round, and so could everything else.
+        let entry = "ab | ab".parse::<Entry>().unwrap();
+        assert!(solve(&alphabet, &entry).unwrap_err().starts_with("ambiguous"));
+        // Two different patterns can't both be a 1.
+        let entry = "ab bc | ab".parse::<Entry>().unwrap();
+        assert!(solve(&alphabet, &entry).is_err());
+        let entry = "abcdefgh | ab".parse::<Entry>().unwrap();
+        assert!(solve(&alphabet, &entry).is_err());
+
+        assert!("ab cd".parse::<Entry>().is_err());
+        assert!(parse_pattern("aab").is_err());
+        assert!(parse_pattern("aB").is_err());
+        assert_eq!(format_pattern(parse_pattern("gca").unwrap()), "acg");
+    }
+}
@@ -1,39 +1,15 @@
-use std::io::BufRead;
+use std::io::Read;
 
-fn main() -> Result<(), std::io::Error> {
-    let stdin = std::io::stdin();
+use day9::Heightmap;
 
-    let heightmap = stdin.lock()
-        .lines()
-        .map(|x| x.unwrap().chars().map(|x| x.to_digit(10).unwrap() as usize).collect())
-        .collect::<Vec<Vec<usize>>>();
+fn main() -> Result<(), String> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).map_err(|e| e.to_string())?;
+    let heightmap = input.parse::<Heightmap>()?;
+    let rules = day9::cli::rules()?;
 
-    let width = heightmap[0].len();
-    let height = heightmap.len();
-    println!("heightmap: {:?} w={} h={}", heightmap, width, height);
-
-    let mut lowest_points = vec![];
-    let mut risk_level = 0;
-    for y in 0..height {
-        for (x, val) in heightmap[y].iter().copied().enumerate() {
-            if x > 0 && heightmap[y][x - 1] <= val {
-                continue;
-            }
-            if x < width - 1 && heightmap[y][x + 1] <= val {
-                continue;
-            }
-            if y > 0 && heightmap[y - 1][x] <= val {
-                continue;
-            }
-            if (y < height - 1) && heightmap[y + 1][x] <= val {
-                continue;
-            }
-            lowest_points.push(val);
-            risk_level += val + 1;
-        }
-    }
-
-    println!("lowest points={:?} risk={}", lowest_points, risk_level);
+    let lowest_points = heightmap.low_areas(&rules).iter().map(|x| heightmap.cells()[x[0]]).collect::<Vec<u8>>();
+    println!("lowest points={:?} risk={}", lowest_points, heightmap.risk_level(&rules));
 
     Ok(())
 }
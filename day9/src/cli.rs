@@ -0,0 +1,23 @@
+use crate::{BasinModel, Connectivity, LowPoints, Rules};
+
+// The rules picked on the command line: --plateaus finds flat-bottomed low areas as well as single
+// low points, --barrier=N walls basins off at height N instead of 9, --connectivity=8 counts
+// diagonal neighbours and --drainage makes basins follow the water downhill.
+pub fn rules() -> Result<Rules, String> {
+    let mut rules = Rules::default();
+    if std::env::args().any(|x| x == "--plateaus") {
+        rules.low_points = LowPoints::Plateau;
+    }
+    if let Some(barrier) = args::value("barrier")? {
+        rules.barrier = barrier;
+    }
+    rules.connectivity = match args::value::<String>("connectivity")?.as_deref() {
+        None | Some("4") => Connectivity::Four,
+        Some("8") => Connectivity::Eight,
+        Some(other) => return Err(format!("--connectivity: expected 4 or 8, got {}", other)),
+    };
+    if std::env::args().any(|x| x == "--drainage") {
+        rules.model = BasinModel::Drainage;
+    }
+    Ok(rules)
+}
@@ -0,0 +1,405 @@
+pub mod cli;
+mod union_find;
+pub mod watershed;
+
+use std::str::FromStr;
+
+use crate::union_find::UnionFind;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LowPoints {
+    // Only cells lower than all their neighbours, so a flat bottom has no low point at all.
+    #[default]
+    Strict,
+    // Regions of cells of the same height with nothing lower around them count as one low area.
+    Plateau,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    // Up, down, left and right.
+    #[default]
+    Four,
+    // Diagonals as well.
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BasinModel {
+    // Every cell below the barrier joins the basins of its neighbours, so a basin is everything walled
+    // in by the barrier.
+    #[default]
+    Barrier,
+    // Water runs from each cell to its lowest neighbour, if that's lower, and a basin is everything
+    // that ends up in the same low area. Flat stretches drain together through their lowest way out.
+    Drainage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub low_points: LowPoints,
+    // Heights at or above this are ridges that aren't part of any basin.
+    pub barrier: u8,
+    pub connectivity: Connectivity,
+    pub model: BasinModel,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules { low_points: LowPoints::default(), barrier: 9, connectivity: Connectivity::default(), model: BasinModel::default() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    width: usize,
+    height: usize,
+    // Row by row.
+    cells: Vec<u8>,
+}
+
+impl FromStr for Heightmap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).collect::<Vec<&str>>();
+        let width = lines.first().ok_or("empty heightmap")?.len();
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(format!("row {} is {} wide, expected {}", y + 1, line.len(), width));
+            }
+            for (x, char) in line.chars().enumerate() {
+                let height = char.to_digit(10).ok_or_else(|| format!("unexpected char at {},{}: {}", x, y, char))?;
+                cells.push(height as u8);
+            }
+        }
+        Ok(Heightmap { width, height: lines.len(), cells })
+    }
+}
+
+impl Heightmap {
+    pub fn new(width: usize, height: usize, cells: Vec<u8>) -> Result<Heightmap, String> {
+        if width == 0 || height == 0 || cells.len() != width * height {
+            return Err(format!("{} heights don't make a {}x{} map", cells.len(), width, height));
+        }
+        Ok(Heightmap { width, height, cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn coords(&self, idx: usize) -> (usize, usize) {
+        (idx % self.width, idx / self.width)
+    }
+
+    // Cells next to idx that are on the map.
+    pub fn neighbours(&self, idx: usize, connectivity: Connectivity) -> impl Iterator<Item = usize> {
+        let (x, y) = self.coords(idx);
+        let (w, h) = (self.width, self.height);
+        let count = match connectivity {
+            Connectivity::Four => 4,
+            Connectivity::Eight => 8,
+        };
+        [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .into_iter()
+            .take(count)
+            .filter_map(move |(dx, dy)| {
+                let (x, y) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                (x < w && y < h).then_some(y * w + x)
+            })
+    }
+
+    // Joins up neighbouring cells of the same height.
+    fn plateaus(&self, connectivity: Connectivity) -> UnionFind {
+        let mut sets = UnionFind::new(self.cells.len());
+        for idx in 0..self.cells.len() {
+            for other in self.neighbours(idx, connectivity) {
+                if other < idx && self.cells[other] == self.cells[idx] {
+                    sets.union(idx, other);
+                }
+            }
+        }
+        sets
+    }
+
+    // The cells of each low area, in the order of their first cell. With strict low points every area
+    // is a single cell.
+    pub fn low_areas(&self, rules: &Rules) -> Vec<Vec<usize>> {
+        if rules.low_points == LowPoints::Strict {
+            return (0..self.cells.len())
+                .filter(|idx| self.neighbours(*idx, rules.connectivity).all(|x| self.cells[x] > self.cells[*idx]))
+                .map(|x| vec![x])
+                .collect();
+        }
+
+        let mut sets = self.plateaus(rules.connectivity);
+        let mut has_lower = vec![false; self.cells.len()];
+        for idx in 0..self.cells.len() {
+            if self.neighbours(idx, rules.connectivity).any(|x| self.cells[x] < self.cells[idx]) {
+                let root = sets.find(idx);
+                has_lower[root] = true;
+            }
+        }
+
+        let mut area_of_root: Vec<Option<usize>> = vec![None; self.cells.len()];
+        let mut areas: Vec<Vec<usize>> = vec![];
+        for idx in 0..self.cells.len() {
+            let root = sets.find(idx);
+            if has_lower[root] {
+                continue;
+            }
+            match area_of_root[root] {
+                Some(area) => areas[area].push(idx),
+                None => {
+                    area_of_root[root] = Some(areas.len());
+                    areas.push(vec![idx]);
+                }
+            }
+        }
+        areas
+    }
+
+    // Each low area counts once, however many cells it has.
+    pub fn risk_level(&self, rules: &Rules) -> u64 {
+        self.low_areas(rules).iter().map(|x| self.cells[x[0]] as u64 + 1).sum()
+    }
+
+    // For every cell, a cell standing for the group it belongs to under the basin model.
+    fn groups(&self, rules: &Rules) -> Vec<usize> {
+        match rules.model {
+            BasinModel::Barrier => {
+                let mut sets = UnionFind::new(self.cells.len());
+                for idx in 0..self.cells.len() {
+                    for other in self.neighbours(idx, rules.connectivity) {
+                        if other < idx && self.cells[idx] < rules.barrier && self.cells[other] < rules.barrier {
+                            sets.union(idx, other);
+                        }
+                    }
+                }
+                (0..self.cells.len()).map(|x| sets.find(x)).collect()
+            }
+            BasinModel::Drainage => {
+                // Each flat stretch drains into its lowest neighbour, the first one row by row if
+                // there's a tie. Every step goes down, so there are at most ten of them to the bottom.
+                let mut sets = self.plateaus(rules.connectivity);
+                let mut exits: Vec<Option<usize>> = vec![None; self.cells.len()];
+                for idx in 0..self.cells.len() {
+                    let root = sets.find(idx);
+                    for other in self.neighbours(idx, rules.connectivity) {
+                        let lower = self.cells[other] < self.cells[idx];
+                        if lower && exits[root].is_none_or(|x| (self.cells[other], other) < (self.cells[x], x)) {
+                            exits[root] = Some(other);
+                        }
+                    }
+                }
+                (0..self.cells.len())
+                    .map(|idx| {
+                        let mut root = sets.find(idx);
+                        while let Some(exit) = exits[root] {
+                            root = sets.find(exit);
+                        }
+                        root
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    // Numbers the groups of cells (see BasinModel) that hold a low area in the order of their first
+    // low area. Groups with no low area below the barrier aren't basins and are left unlabelled along
+    // with the barriers.
+    pub fn basins(&self, rules: &Rules) -> Basins {
+        let groups = self.groups(rules);
+
+        let mut basin_of_group = vec![None; self.cells.len()];
+        let mut basins = vec![];
+        for low_point in self.low_areas(rules).into_iter().map(|x| x[0]) {
+            let group = groups[low_point];
+            if self.cells[low_point] < rules.barrier && basin_of_group[group].is_none() {
+                basin_of_group[group] = Some(basins.len());
+                basins.push(Basin { low_point, size: 0 });
+            }
+        }
+
+        let labels = (0..self.cells.len())
+            .map(|idx| {
+                let label = if self.cells[idx] < rules.barrier { basin_of_group[groups[idx]] } else { None };
+                if let Some(label) = label {
+                    basins[label].size += 1;
+                }
+                label
+            })
+            .collect();
+        Basins { width: self.width, labels, basins }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Basin {
+    // The first cell of its first low area, row by row.
+    pub low_point: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Basins {
+    width: usize,
+    // Which basin each cell is in, row by row.
+    labels: Vec<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl Basins {
+    pub fn labels(&self) -> &[Option<usize>] {
+        &self.labels
+    }
+
+    pub fn basins(&self) -> &[Basin] {
+        &self.basins
+    }
+
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        self.labels[y * self.width + x]
+    }
+
+    // Product of the sizes of the n largest basins.
+    pub fn largest_product(&self, n: usize) -> u128 {
+        let mut sizes = self.basins.iter().map(|x| x.size).collect::<Vec<usize>>();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.iter().take(n).map(|x| *x as u128).product()
+    }
+
+    // The heightmap with every cell outside a basin blanked out.
+    pub fn render(&self, heightmap: &Heightmap) -> String {
+        let mut out = String::with_capacity(self.labels.len() + heightmap.height);
+        for (idx, label) in self.labels.iter().enumerate() {
+            out.push(match label {
+                Some(_) => (b'0' + heightmap.cells[idx]) as char,
+                None => ' ',
+            });
+            if (idx + 1) % self.width == 0 {
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Basin, BasinModel, Connectivity, Heightmap, LowPoints, Rules};
+
+    const EXAMPLE: &str = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678\n";
+
+    #[test]
+    fn example() {
+        let map = EXAMPLE.parse::<Heightmap>().unwrap();
+        let low_points = map.low_areas(&Rules::default()).iter().map(|x| map.coords(x[0])).collect::<Vec<(usize, usize)>>();
+        assert_eq!(low_points, vec![(1, 0), (9, 0), (2, 2), (6, 4)]);
+        assert_eq!(map.risk_level(&Rules::default()), 15);
+
+        let basins = map.basins(&Rules::default());
+        assert_eq!(basins.basins(), &[Basin { low_point: 1, size: 3 }, Basin { low_point: 9, size: 9 }, Basin { low_point: 22, size: 14 }, Basin { low_point: 46, size: 9 }]);
+        assert_eq!(basins.largest_product(3), 1134);
+        assert_eq!((basins.label(0, 0), basins.label(2, 0), basins.label(0, 3)), (Some(0), None, Some(2)));
+        assert_eq!(basins.render(&map).lines().next(), Some("21   43210"));
+    }
+
+    #[test]
+    fn large() {
+        // A winding corridor through a million cells, which is one basin however far it goes.
+        let (w, h) = (1000, 1000);
+        let cells = (0..w * h)
+            .map(|idx| {
+                let (x, y) = (idx % w, idx / w);
+                let open = y % 2 == 0 || (y % 4 == 1 && x == w - 1) || (y % 4 == 3 && x == 0);
+                if open { (x % 9) as u8 } else { 9 }
+            })
+            .collect();
+        let map = Heightmap::new(w, h, cells).unwrap();
+        let basins = map.basins(&Rules::default());
+        assert_eq!(basins.basins().len(), 1);
+        assert_eq!(basins.basins()[0].size, w * h / 2 + h / 2);
+    }
+
+    #[test]
+    fn plateaus() {
+        let strict = Rules::default();
+        let plateau = Rules { low_points: LowPoints::Plateau, ..Rules::default() };
+
+        let map = "99999\n91129\n99999\n".parse::<Heightmap>().unwrap();
+        assert_eq!(map.low_areas(&strict), Vec::<Vec<usize>>::new());
+        assert_eq!(map.basins(&strict).basins(), &[]);
+        assert_eq!(map.low_areas(&plateau), vec![vec![6, 7]]);
+        assert_eq!(map.risk_level(&plateau), 2);
+        assert_eq!(map.basins(&plateau).basins(), &[Basin { low_point: 6, size: 3 }]);
+
+        // Flat but with a way down isn't low.
+        let map = "3321\n".parse::<Heightmap>().unwrap();
+        assert_eq!(map.low_areas(&plateau), vec![vec![3]]);
+        let map = "1121\n9999\n2011\n".parse::<Heightmap>().unwrap();
+        assert_eq!(map.low_areas(&plateau), vec![vec![0, 1], vec![3], vec![9]]);
+        assert_eq!(map.low_areas(&strict), vec![vec![3], vec![9]]);
+
+        // The example has no plateaus, so nothing changes.
+        let map = EXAMPLE.parse::<Heightmap>().unwrap();
+        assert_eq!(map.basins(&plateau), map.basins(&strict));
+    }
+
+    #[test]
+    fn rules() {
+        let map = EXAMPLE.parse::<Heightmap>().unwrap();
+
+        // A lower barrier walls off the 8s too.
+        let low = Rules { barrier: 8, ..Rules::default() };
+        let sizes = map.basins(&low).basins().iter().map(|x| x.size).collect::<Vec<usize>>();
+        assert_eq!(sizes, vec![3, 9, 7, 6]);
+
+        // Diagonals get through every gap between the 9s, so it's all one basin.
+        let eight = Rules { connectivity: Connectivity::Eight, ..Rules::default() };
+        assert_eq!(map.basins(&eight).basins(), &[Basin { low_point: 1, size: 35 }]);
+
+        // Every cell in the example already drains into its own basin's low point.
+        let drainage = Rules { model: BasinModel::Drainage, ..Rules::default() };
+        assert_eq!(map.basins(&drainage), map.basins(&Rules::default()));
+    }
+
+    #[test]
+    fn drainage() {
+        // Two valleys with only a low ridge between them: filling joins them, draining doesn't. The 4
+        // on the ridge could go either way and goes to the first.
+        let map = "4104014\n5215125\n".parse::<Heightmap>().unwrap();
+        assert_eq!(map.basins(&Rules::default()).basins(), &[Basin { low_point: 2, size: 14 }]);
+        let drainage = Rules { model: BasinModel::Drainage, ..Rules::default() };
+        let basins = map.basins(&drainage);
+        assert_eq!(basins.basins(), &[Basin { low_point: 2, size: 8 }, Basin { low_point: 4, size: 6 }]);
+        assert_eq!(basins.labels()[..7], [Some(0), Some(0), Some(0), Some(0), Some(1), Some(1), Some(1)]);
+
+        // A flat shelf drains through its lowest edge as one.
+        let map = "9999999\n3222220\n9999999\n".parse::<Heightmap>().unwrap();
+        let basins = map.basins(&drainage);
+        assert_eq!(basins.basins(), &[Basin { low_point: 13, size: 7 }]);
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!("123\n45\n".parse::<Heightmap>().is_err());
+        assert!("12a\n".parse::<Heightmap>().is_err());
+        assert!("".parse::<Heightmap>().is_err());
+        assert!(Heightmap::new(2, 2, vec![1, 2, 3]).is_err());
+    }
+}
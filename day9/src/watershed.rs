@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+
+use crate::union_find::UnionFind;
+use crate::{Heightmap, Rules};
+
+// A pool of water at some level: either a new one filling a low area, or where pools that were apart
+// at the level below run together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeNode {
+    pub level: u8,
+    // Cells under water in the pool at this level.
+    pub size: usize,
+    // Indexes of the pools that ran together, none for a new pool.
+    pub children: Vec<usize>,
+    // The first cell of a new pool, row by row.
+    pub low_point: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeTree {
+    width: usize,
+    // Children always come before their parent.
+    nodes: Vec<MergeNode>,
+}
+
+impl MergeTree {
+    pub fn nodes(&self) -> &[MergeNode] {
+        &self.nodes
+    }
+
+    // Pools that are still apart at the top level.
+    pub fn roots(&self) -> Vec<usize> {
+        let mut merged = vec![false; self.nodes.len()];
+        for node in self.nodes.iter() {
+            for child in node.children.iter() {
+                merged[*child] = true;
+            }
+        }
+        (0..self.nodes.len()).filter(|x| !merged[*x]).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let nodes = self.nodes.iter().enumerate()
+            .map(|(id, node)| format!(
+                "{{\"id\":{},\"level\":{},\"size\":{},\"low_point\":{},\"children\":[{}]}}",
+                id,
+                node.level,
+                node.size,
+                node.low_point.map_or("null".to_string(), |x| format!("[{},{}]", x % self.width, x / self.width)),
+                node.children.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(","),
+            ))
+            .collect::<Vec<String>>();
+        format!(
+            "{{\"nodes\":[\n{}\n],\"roots\":[{}]}}\n",
+            nodes.join(",\n"),
+            self.roots().iter().map(|x| x.to_string()).collect::<Vec<String>>().join(","),
+        )
+    }
+}
+
+impl Heightmap {
+    // Floods the map one level at a time from 0 up to `top`, keeping track of which pools each cell
+    // coming under water touches. A group of newly flooded cells touching no pool is a new one, and one
+    // touching several joins them. Neighbours are as in the rules; the barrier and basin model don't
+    // come into it, since the water goes over everything in the end.
+    pub fn merge_tree(&self, rules: &Rules, top: u8) -> MergeTree {
+        let mut by_level = vec![vec![]; top as usize + 1];
+        for (idx, height) in self.cells.iter().enumerate() {
+            if *height <= top {
+                by_level[*height as usize].push(idx);
+            }
+        }
+
+        let mut sets = UnionFind::new(self.cells.len());
+        let mut node_of_root: Vec<Option<usize>> = vec![None; self.cells.len()];
+        let mut nodes: Vec<MergeNode> = vec![];
+        for (level, cells) in by_level.iter().enumerate() {
+            // The pools next to each new cell, before any of them get joined.
+            let touching = cells.iter()
+                .map(|idx| {
+                    self.neighbours(*idx, rules.connectivity)
+                        .filter(|x| self.cells[*x] < level as u8)
+                        .filter_map(|x| node_of_root[sets.find(x)])
+                        .collect::<Vec<usize>>()
+                })
+                .collect::<Vec<Vec<usize>>>();
+
+            for idx in cells.iter().copied() {
+                for other in self.neighbours(idx, rules.connectivity) {
+                    if self.cells[other] <= level as u8 {
+                        sets.union(idx, other);
+                    }
+                }
+            }
+
+            let mut pools: Vec<(usize, usize, BTreeSet<usize>)> = vec![];
+            let mut pool_of_root: Vec<Option<usize>> = vec![None; self.cells.len()];
+            for (idx, touching) in cells.iter().zip(touching) {
+                let root = sets.find(*idx);
+                let pool = *pool_of_root[root].get_or_insert_with(|| {
+                    pools.push((root, *idx, BTreeSet::new()));
+                    pools.len() - 1
+                });
+                pools[pool].2.extend(touching);
+            }
+            for (root, first, touching) in pools {
+                let size = sets.size(root);
+                node_of_root[root] = match touching.len() {
+                    0 => {
+                        nodes.push(MergeNode { level: level as u8, size, children: vec![], low_point: Some(first) });
+                        Some(nodes.len() - 1)
+                    }
+                    1 => touching.into_iter().next(),
+                    _ => {
+                        nodes.push(MergeNode { level: level as u8, size, children: touching.into_iter().collect(), low_point: None });
+                        Some(nodes.len() - 1)
+                    }
+                };
+            }
+        }
+        MergeTree { width: self.width, nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::watershed::MergeNode;
+    use crate::{Heightmap, Rules};
+
+    #[test]
+    fn example() {
+        let map = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678\n".parse::<Heightmap>().unwrap();
+        let tree = map.merge_tree(&Rules::default(), 9);
+        let leaves = tree.nodes().iter().filter_map(|x| x.low_point.map(|p| (x.level, map.coords(p)))).collect::<Vec<(u8, (usize, usize))>>();
+        assert_eq!(leaves, vec![(0, (9, 0)), (1, (1, 0)), (5, (2, 2)), (5, (6, 4))]);
+        assert_eq!(tree.roots().len(), 1);
+        // The basins are walled in by 9s, so they all run together at once at the very top.
+        assert_eq!(tree.nodes()[4], MergeNode { level: 9, size: 50, children: vec![0, 1, 2, 3], low_point: None });
+        assert_eq!(map.merge_tree(&Rules::default(), 8).roots(), vec![0, 1, 2, 3]);
+        assert!(tree.to_json().starts_with("{\"nodes\":[\n{\"id\":0,\"level\":0,\"size\":1,\"low_point\":[9,0],\"children\":[]},\n"));
+    }
+
+    #[test]
+    fn merges() {
+        // Two pools that meet over the 3, then take in the one at 0 over the 5s.
+        let map = "1312505\n".parse::<Heightmap>().unwrap();
+        let tree = map.merge_tree(&Rules::default(), 9);
+        assert_eq!(tree.nodes(), &[
+            MergeNode { level: 0, size: 1, children: vec![], low_point: Some(5) },
+            MergeNode { level: 1, size: 1, children: vec![], low_point: Some(0) },
+            MergeNode { level: 1, size: 1, children: vec![], low_point: Some(2) },
+            MergeNode { level: 3, size: 4, children: vec![1, 2], low_point: None },
+            MergeNode { level: 5, size: 7, children: vec![0, 3], low_point: None },
+        ]);
+        assert_eq!(tree.roots(), vec![4]);
+        assert_eq!(map.merge_tree(&Rules::default(), 4).roots(), vec![0, 3]);
+    }
+}